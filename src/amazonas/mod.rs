@@ -1,6 +1,8 @@
 use pathfinding::num_traits::abs;
 use std::io::Write;
 
+use crate::terminal::{self, Colour, Glyph, Style};
+
 type Number = i32;
 type Coordinates = (Number, Number);
 
@@ -344,12 +346,36 @@ impl Board {
         writeln!(output)?;
         Ok(())
     }
+
+    pub fn render_stdout(&self, prefix: &str) {
+        let _ = self.render(&mut std::io::stdout(), prefix, &Style::for_stdout());
+    }
+
+    // LIKE dump, WITH THE GLYPHS AND COLOURS OF THE STYLE
+    pub fn render<W: Write>(&self, output: &mut W, prefix: &str, style: &Style) -> std::io::Result<()> {
+        let size = (self.board_size as usize, self.board_size as usize);
+        terminal::write_grid(output, prefix, " ", size, style, |column, line| {
+            let c = (column as Number, line as Number);
+            if self.next_cell_to_fill == Some(c) {
+                Glyph::new('x', '✕', Some(Colour::Red))
+            } else if self.trees.contains(&c) {
+                Glyph::new('T', '♣', Some(Colour::Green))
+            } else if self.amazons.contains(&c) {
+                Glyph::new('A', '♛', Some(Colour::Yellow))
+            } else {
+                Glyph::new('.', '·', Some(Colour::Gray))
+            }
+        })?;
+        writeln!(output)?;
+        writeln!(output)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::Board;
     use crate::amazonas::Number;
+    use crate::terminal::Style;
     use ntest::assert_true;
 
     #[test]
@@ -386,6 +412,30 @@ mod test {
             .is_some());
     }
 
+    #[test]
+    fn render() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+        let lines = vec!(
+            "T . . . ",
+            "A . . x ",
+            ". . . . ",
+            ". . . . "
+        );
+        let board = Board::from_lines(lines);
+        let mut dump = vec![];
+        board.dump(&mut dump, "> ").unwrap();
+        let mut plain = vec![];
+        board.render(&mut plain, "> ", &Style::plain()).unwrap();
+        assert_eq!(plain, dump);
+
+        let unicode = Style { unicode: true, colour: false };
+        let mut output = vec![];
+        board.render(&mut output, "", &unicode).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().next().unwrap(), "♣ · · · ");
+        assert_eq!(output.lines().nth(1).unwrap(), "♛ · · ✕ ");
+    }
+
     #[test]
     fn from_lines_not_threatened() {
         #[cfg_attr(rustfmt, rustfmt::skip)]
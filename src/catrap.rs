@@ -1,29 +1,62 @@
 
 
 mod state;
+mod batch;
 mod block;
+mod cli;
+mod cost;
+#[allow(dead_code)]
+mod diff;
+#[allow(dead_code)]
+mod difficulty;
 mod stage;
 mod direction;
-
-use pathfinding::prelude::astar;
-
+mod editor;
+mod event;
+#[allow(dead_code)]
+mod exploration;
+mod gameboy;
+#[allow(dead_code)]
+mod generator;
+#[allow(dead_code)]
+mod gif;
+mod hint;
+mod ida_star;
+mod macro_moves;
+#[allow(dead_code)]
+mod minimizer;
+mod moves;
+#[allow(dead_code)]
+mod optimal;
+mod pack;
+#[cfg(test)]
+mod properties;
+mod play;
+mod random;
+mod solver;
+mod staged;
+#[allow(dead_code)]
+mod svg;
+
+use solver::SolverConfig;
 use state::State;
+use crate::terminal::Style;
 
 
 
 pub fn solve_catrap( state: &State ) -> Option<Vec<State>> {
+    solver::solve( state, &SolverConfig::default() ).map( |solution| solution.states )
+}
 
-    let successors = |st:&State|{
-        st.reachable_states_one_move().into_iter().map( |s| (s,1) )
-    };
-    let heuristic = |_st:&State| 0;
-    let success = |st:&State| st.ghosts_count() == 0;
-    let ret = astar( state, successors, heuristic, success );
 
-    ret.map( |(states,_cost)| states )
+// RUNS THE COMMAND LINE, PLAYING WITH STDIN AND STDOUT
+pub(crate) fn run_cli( args: &[String] ) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    cli::run( args, stdin.lock(), &mut std::io::stdout(), &Style::for_stdout() )
 }
 
 
+#[allow(dead_code, clippy::deprecated_cfg_attr, clippy::assertions_on_constants)]
 pub(crate) fn level_80(){
     #[cfg_attr(rustfmt, rustfmt::skip)]
         let strings = vec![
@@ -49,6 +82,7 @@ pub(crate) fn level_80(){
 
 
 #[cfg(test)]
+#[allow(clippy::deprecated_cfg_attr, clippy::assertions_on_constants)]
 mod tests {
     use super::super::catrap::solve_catrap;
     use super::state::State;
@@ -93,6 +127,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn teleport_to_ghost(){
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ 3W   W",
+            "WWWWW   W",
+            "W 3 G   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+
+        match solve_catrap(&state){
+            None => assert!(false),
+            Some(states) => {
+                states.iter().for_each( |s| s.dump_stdout() );
+                assert_eq!(states.len(), 5);
+            }
+        }
+    }
+
     #[test]
     fn level_80(){
         super::level_80();
@@ -0,0 +1,308 @@
+// SOLVES EVERY LEVEL OF THE FILES IN A DIRECTORY, EACH FILE A PACK OR A SINGLE LEVEL,
+// AND REPORTS HOW EACH SEARCH WENT AS CSV OR JSON
+
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use super::pack::Pack;
+use super::solver::{search, SolverConfig};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LevelReport {
+    pub file: String,
+    pub level: String,
+    pub solved: bool,
+    // THE SEARCH GAVE UP: A LEVEL NOT SOLVED MAY STILL HAVE A SOLUTION
+    pub limit_reached: bool,
+    pub moves: Option<usize>,
+    pub expanded: usize,
+    pub time: Duration,
+    // WHY THE FILE COULD NOT BE READ, ITS LEVEL IS THEN EMPTY
+    pub error: Option<String>,
+}
+
+impl LevelReport {
+    fn failed(file: String, error: String) -> LevelReport {
+        LevelReport {
+            file,
+            level: String::new(),
+            solved: false,
+            limit_reached: false,
+            moves: None,
+            expanded: 0,
+            time: Duration::ZERO,
+            error: Some(error),
+        }
+    }
+}
+
+const COLUMNS: [&str; 8] = [
+    "file",
+    "level",
+    "solved",
+    "limit_reached",
+    "moves",
+    "expanded",
+    "time_ms",
+    "error",
+];
+
+// RETURNS: a report for each level, the files in the order of their names. HIDDEN
+// FILES AND DIRECTORIES ARE SKIPPED, A FILE THAT CAN NOT BE READ GETS A REPORT WITH
+// ITS ERROR
+pub fn solve_directory(
+    directory: &Path,
+    config: &SolverConfig,
+) -> std::io::Result<Vec<LevelReport>> {
+    let mut paths = vec![];
+    let mut reports = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                let file = directory.display().to_string();
+                reports.push(LevelReport::failed(file, e.to_string()));
+                continue;
+            }
+        };
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if path.is_file() && !hidden {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    for path in paths {
+        let file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let pack = match Pack::load(&path) {
+            Ok(pack) => pack,
+            Err(e) => {
+                reports.push(LevelReport::failed(file, e.to_string()));
+                continue;
+            }
+        };
+        for level in pack.levels.iter() {
+            let outcome = search(&level.state(), config);
+            reports.push(LevelReport {
+                file: file.clone(),
+                level: level.name.clone(),
+                solved: outcome.solution.is_some(),
+                limit_reached: outcome.limit_reached,
+                moves: outcome.solution.map(|s| s.moves.len()),
+                expanded: outcome.expanded,
+                time: outcome.elapsed,
+                error: None,
+            });
+        }
+    }
+    Ok(reports)
+}
+
+fn fields(report: &LevelReport) -> [String; 8] {
+    [
+        report.file.clone(),
+        report.level.clone(),
+        report.solved.to_string(),
+        report.limit_reached.to_string(),
+        report.moves.map(|m| m.to_string()).unwrap_or_default(),
+        report.expanded.to_string(),
+        report.time.as_millis().to_string(),
+        report.error.clone().unwrap_or_default(),
+    ]
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn write_csv<W: Write>(reports: &[LevelReport], output: &mut W) -> std::io::Result<()> {
+    writeln!(output, "{}", COLUMNS.join(","))?;
+    for report in reports {
+        let line: Vec<String> = fields(report).iter().map(|f| csv_field(f)).collect();
+        writeln!(output, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json + "\""
+}
+
+// AN ARRAY WITH AN OBJECT PER LEVEL, moves IS null WHEN THE LEVEL WAS NOT SOLVED AND
+// error IS null WHEN THE FILE WAS READ
+pub fn write_json<W: Write>(reports: &[LevelReport], output: &mut W) -> std::io::Result<()> {
+    writeln!(output, "[")?;
+    for (index, report) in reports.iter().enumerate() {
+        let moves = report
+            .moves
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| "null".to_string());
+        let error = report
+            .error
+            .as_deref()
+            .map(json_string)
+            .unwrap_or_else(|| "null".to_string());
+        writeln!(
+            output,
+            "  {{\"file\": {}, \"level\": {}, \"solved\": {}, \"limit_reached\": {}, \"moves\": {}, \"expanded\": {}, \"time_ms\": {}, \"error\": {}}}{}",
+            json_string(&report.file),
+            json_string(&report.level),
+            report.solved,
+            report.limit_reached,
+            moves,
+            report.expanded,
+            report.time.as_millis(),
+            error,
+            if index + 1 < reports.len() { "," } else { "" }
+        )?;
+    }
+    writeln!(output, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::solver::{Limits, SolverConfig};
+    use super::{solve_directory, write_csv, write_json, LevelReport};
+
+    const PACK: &str = "pack: Easy
+# Only right
+WWWWWWWWW
+W@ G    W
+WWWWWWWWW
+# Two ghosts
+WWWWWWWWW
+W@ G    W
+WWWW    W
+WG      W
+WWWWWWWWW
+";
+
+    const STUCK: &str = "WWWWW\nW@WGW\nWWWWW\n";
+
+    #[test]
+    fn directory() {
+        let directory = std::env::temp_dir().join(format!("catrap-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a_easy.txt"), PACK).unwrap();
+        std::fs::write(directory.join("b_stuck.txt"), STUCK).unwrap();
+        std::fs::write(directory.join("c_broken.txt"), [0xff, 0xfe]).unwrap();
+        std::fs::write(directory.join("d_wide.txt"), "W@ G          W\n").unwrap();
+        std::fs::write(directory.join(".hidden"), "not a level").unwrap();
+
+        let reports = solve_directory(&directory, &SolverConfig::default()).unwrap();
+        let levels: Vec<(&str, bool, Option<usize>)> = reports
+            .iter()
+            .map(|r| (r.level.as_str(), r.solved, r.moves))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                ("Only right", true, Some(2)),
+                ("Two ghosts", true, Some(6)),
+                ("b_stuck", false, None),
+                ("", false, None),
+                ("", false, None)
+            ]
+        );
+        assert!(reports.iter().all(|r| !r.limit_reached));
+        assert!(reports[..3].iter().all(|r| r.error.is_none()));
+        assert_eq!(reports[3].file, "c_broken.txt");
+        assert!(reports[3].error.is_some());
+        assert_eq!(reports[4].file, "d_wide.txt");
+        assert!(reports[4].error.is_some());
+
+        let config = SolverConfig {
+            limits: Limits {
+                max_expanded: Some(2),
+                max_time: None,
+            },
+            ..Default::default()
+        };
+        let reports = solve_directory(&directory, &config).unwrap();
+        assert!(reports[0].solved);
+        assert!(!reports[1].solved && reports[1].limit_reached);
+        assert_eq!(reports[1].expanded, 2);
+        assert!(!reports[2].solved && !reports[2].limit_reached);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn report_formats() {
+        let reports = vec![
+            LevelReport {
+                file: "pack.txt".to_string(),
+                level: "Say \"hi\", twice".to_string(),
+                solved: true,
+                limit_reached: false,
+                moves: Some(12),
+                expanded: 340,
+                time: Duration::from_millis(25),
+                error: None,
+            },
+            LevelReport {
+                file: "hard.txt".to_string(),
+                level: "hard".to_string(),
+                solved: false,
+                limit_reached: true,
+                moves: None,
+                expanded: 1000,
+                time: Duration::from_millis(3),
+                error: None,
+            },
+            LevelReport {
+                file: "broken.txt".to_string(),
+                level: String::new(),
+                solved: false,
+                limit_reached: false,
+                moves: None,
+                expanded: 0,
+                time: Duration::ZERO,
+                error: Some("stream did not contain valid UTF-8".to_string()),
+            },
+        ];
+        let mut csv = vec![];
+        write_csv(&reports, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "file,level,solved,limit_reached,moves,expanded,time_ms,error
+pack.txt,\"Say \"\"hi\"\", twice\",true,false,12,340,25,
+hard.txt,hard,false,true,,1000,3,
+broken.txt,,false,false,,0,0,stream did not contain valid UTF-8
+"
+        );
+
+        let mut json = vec![];
+        write_json(&reports, &mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(
+            json.starts_with("[\n  {\"file\": \"pack.txt\", \"level\": \"Say \\\"hi\\\", twice\",")
+        );
+        assert!(json
+            .contains("\"moves\": null, \"expanded\": 1000, \"time_ms\": 3, \"error\": null},\n"));
+        assert!(json.ends_with("\"error\": \"stream did not contain valid UTF-8\"}\n]\n"));
+    }
+}
@@ -1,5 +1,8 @@
-#[derive(Clone, Copy, Debug,Eq,PartialEq,Hash)]
+use crate::terminal::{Colour, Glyph};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
 pub enum Block {
+    #[default]
     Wall,
     SandWall,
     FallingGhost,
@@ -8,24 +11,24 @@ pub enum Block {
     Stair,
     Hero,
     Empty,
+    Key(u8),
+    Door(u8),
+    CrumblingFloor,
+    Teleporter(u8),
 }
 
-impl Default for Block {
-    fn default() -> Self {
-        Block::Wall
-    }
-}
 impl Block {
     pub fn is_empty(block: Block) -> bool {
-        match block {
-            Block::Empty => true,
-            _ => false,
-        }
+        matches!(block, Block::Empty)
     }
 
     pub fn from_char(c: char) -> Block {
+        Block::try_from_char(c).expect("Undefined character to block conversion")
+    }
+
+    pub fn try_from_char(c: char) -> Option<Block> {
         use Block::*;
-        match c {
+        let block = match c {
             'W' => Wall,
             '*' => SandWall,
             'F' => FallingGhost,
@@ -34,13 +37,18 @@ impl Block {
             '=' => Stair,
             ' ' => Empty,
             '@' => Hero,
-            _ => panic!("Undefined character to block conversion"),
-        }
+            '-' => CrumblingFloor,
+            '0'..='9' => Teleporter(c as u8 - b'0'),
+            'a'..='d' => Key(c as u8 - b'a'),
+            'A'..='D' => Door(c as u8 - b'A'),
+            _ => return None,
+        };
+        Some(block)
     }
 
-    pub fn to_char(&self) -> char {
+    pub fn to_char(self) -> char {
         use Block::*;
-        match *self {
+        match self {
             Wall => 'W',
             SandWall => '*',
             FallingGhost => 'F',
@@ -49,31 +57,43 @@ impl Block {
             Stair => '=',
             Empty => ' ',
             Hero => '@',
+            Key(kind) => (b'a' + kind) as char,
+            Door(kind) => (b'A' + kind) as char,
+            CrumblingFloor => '-',
+            Teleporter(label) => (b'0' + label) as char,
         }
     }
 
     pub fn is_fall(block: Block) -> bool {
-        match block {
-            Block::FallingGhost => true,
-            Block::Rock => true,
-            Block::Hero => true,
-            _ => false,
-        }
+        matches!(block, Block::FallingGhost | Block::Rock | Block::Hero)
     }
 
     pub fn is_hero(block: Block) -> bool {
-        match block {
-            Block::Hero => true,
-            _ => false,
-        }
+        matches!(block, Block::Hero)
     }
 
 
     pub fn is_ghost(block: Block) -> bool {
-        match block {
-            Block::FallingGhost => true,
-            Block::Ghost => true,
-            _ => false,
-        }
+        matches!(block, Block::FallingGhost | Block::Ghost)
+    }
+
+    // RETURNS: the glyph of the block for a terminal, to_char when unicode is not used
+    pub fn glyph(&self) -> Glyph {
+        use Block::*;
+        let (unicode, colour) = match *self {
+            Wall => ('█', Some(Colour::Gray)),
+            SandWall => ('▒', Some(Colour::Yellow)),
+            FallingGhost => ('Ω', Some(Colour::Cyan)),
+            Ghost => ('Ω', Some(Colour::White)),
+            Rock => ('●', Some(Colour::Red)),
+            Stair => ('╫', Some(Colour::Yellow)),
+            Hero => ('☻', Some(Colour::Green)),
+            Empty => (' ', None),
+            // KEYS, DOORS AND TELEPORTERS KEEP THEIR LETTER OR DIGIT, THAT TELLS THEIR KIND
+            Key(_) | Door(_) => (self.to_char(), Some(Colour::Blue)),
+            CrumblingFloor => ('░', Some(Colour::Gray)),
+            Teleporter(_) => (self.to_char(), Some(Colour::Magenta)),
+        };
+        Glyph::new(self.to_char(), unicode, colour)
     }
 }
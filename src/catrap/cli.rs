@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::batch::{solve_directory, write_csv, write_json};
+use super::cost::{CostModel, MoveCount, MovesAndSwitches, Pushes, SandDug, Switches};
+use super::editor::{edit, Editor};
+use super::gameboy::{parse_dump, stages_from_bytes, to_pack, TileMap};
+use super::pack::{solve_pack, Pack, Progress};
+use super::play::play;
+use super::solver::{Algorithm, Limits, SolverConfig};
+use crate::terminal::Style;
+
+const USAGE: &str = "usage:
+  catrap list <pack> [--save <file>]           the levels of a pack and the ones solved
+  catrap play <pack> [<level>] [--save <file>] [<solver options>]
+                                               plays a level, the first unsolved by default,
+                                               with hints of the solver
+  catrap solve <pack> [<solver options>]       solves every level of a pack
+  catrap batch <directory> [<solver options>] [--max-states <n>] [--max-seconds <s>] [--json]
+               [--output <file>]               solves the levels of every file in a directory
+                                               and writes a CSV, or JSON, report
+  catrap import <dump> --tiles <file> [--size <width>x<height>] [--output <file>]
+                                               turns a dump of the Game Boy stages into a pack,
+                                               a raw dump of consecutive stages with --size
+  catrap edit <pack> [<level>]                 edits a level of a pack, a new one if it is not
+                                               there, and saves it in the pack
+solver options:
+  --cost <model>                               what the solutions minimize: moves (default),
+                                               moves-and-switches, sand, pushes or switches
+  --one-hero                                   moves one hero at a time, switching between them
+  --algorithm <algorithm>                      astar (default), ida[:<states>] to remember at
+                                               most <states> states, or staged[:<subgoals>] to
+                                               clear the ghosts one at a time, trying up to
+                                               <subgoals> ghosts each time";
+
+// THE SAVE FILE USED WHEN --save IS NOT GIVEN
+const DEFAULT_SAVE: &str = "catrap.save";
+// OPTIONS FOLLOWED BY A VALUE, AND FLAGS
+const OPTIONS: [&str; 8] = [
+    "--save",
+    "--cost",
+    "--algorithm",
+    "--max-states",
+    "--max-seconds",
+    "--output",
+    "--tiles",
+    "--size",
+];
+const FLAGS: [&str; 2] = ["--json", "--one-hero"];
+
+struct Arguments<'a> {
+    positional: Vec<&'a str>,
+    options: HashMap<&'a str, &'a str>,
+}
+
+impl Arguments<'_> {
+    fn save(&self) -> &str {
+        self.options.get("--save").copied().unwrap_or(DEFAULT_SAVE)
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.options.contains_key(flag)
+    }
+
+    fn number<T: FromStr>(&self, option: &str) -> Result<Option<T>, String> {
+        self.options
+            .get(option)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("{} needs a number, not {}", option, value))
+            })
+            .transpose()
+    }
+}
+
+fn parse_arguments(args: &[String]) -> Result<Arguments<'_>, String> {
+    let mut arguments = Arguments {
+        positional: vec![],
+        options: HashMap::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if OPTIONS.contains(&arg.as_str()) {
+            let value = args.next().ok_or(format!("{} needs a value", arg))?;
+            arguments.options.insert(arg, value);
+        } else if FLAGS.contains(&arg.as_str()) {
+            arguments.options.insert(arg, "");
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option {}\n{}", arg, USAGE));
+        } else {
+            arguments.positional.push(arg);
+        }
+    }
+    Ok(arguments)
+}
+
+fn cost_model(name: &str) -> Result<Rc<dyn CostModel>, String> {
+    Ok(match name {
+        "moves" => Rc::new(MoveCount),
+        "moves-and-switches" => Rc::new(MovesAndSwitches),
+        "sand" => Rc::new(SandDug),
+        "pushes" => Rc::new(Pushes),
+        "switches" => Rc::new(Switches),
+        _ => return Err(format!("unknown cost model {}\n{}", name, USAGE)),
+    })
+}
+
+// THE STATES IDA* REMEMBERS WHEN --algorithm ida DOES NOT SAY HOW MANY
+const IDA_TABLE_SIZE: usize = 1_000_000;
+// THE SUBGOALS TRIED IN EACH STAGE WHEN --algorithm staged DOES NOT SAY HOW MANY
+const STAGED_ALTERNATIVES: usize = 3;
+
+fn algorithm(text: &str) -> Result<Algorithm, String> {
+    let (name, number) = match text.split_once(':') {
+        Some((name, number)) => (name, Some(number)),
+        None => (text, None),
+    };
+    let size = |default: usize| match number {
+        Some(size) => size
+            .parse()
+            .map_err(|_| format!("--algorithm needs a number after {}:, not {}", name, size)),
+        None => Ok(default),
+    };
+    match name {
+        "astar" if number.is_none() => Ok(Algorithm::AStar),
+        "ida" => Ok(Algorithm::IdaStar {
+            table_size: size(IDA_TABLE_SIZE)?,
+        }),
+        "staged" => Ok(Algorithm::Staged {
+            alternatives: size(STAGED_ALTERNATIVES)?,
+        }),
+        _ => Err(format!("unknown algorithm {}\n{}", text, USAGE)),
+    }
+}
+
+// THE SOLVER CONFIGURATION OF THE SOLVER OPTIONS
+fn solver_config(arguments: &Arguments) -> Result<SolverConfig, String> {
+    let mut config = SolverConfig {
+        one_hero_at_a_time: arguments.has_flag("--one-hero"),
+        ..Default::default()
+    };
+    if let Some(name) = arguments.options.get("--cost") {
+        config.cost_model = cost_model(name)?;
+    }
+    if let Some(text) = arguments.options.get("--algorithm") {
+        config.algorithm = algorithm(text)?;
+    }
+    Ok(config)
+}
+
+fn load_pack(path: &str) -> Result<Pack, String> {
+    Pack::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn load_progress(path: &str) -> Result<Progress, String> {
+    Progress::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn list<W: Write>(pack: &Pack, progress: &Progress, output: &mut W) -> std::io::Result<()> {
+    writeln!(
+        output,
+        "{}: {} of {} solved",
+        pack.name,
+        progress.solved_count(pack),
+        pack.levels.len()
+    )?;
+    for (index, level) in pack.levels.iter().enumerate() {
+        let best = progress
+            .best(&pack.name, &level.name)
+            .map(|b| format!("best {}", b))
+            .unwrap_or_else(|| "-".to_string());
+        let par = level.par.map(|p| format!("par {}", p)).unwrap_or_default();
+        writeln!(
+            output,
+            "{:3} {:30} {:8} {}",
+            index + 1,
+            level.name,
+            best,
+            par
+        )?;
+    }
+    Ok(())
+}
+
+fn solve<W: Write>(pack: &Pack, arguments: &Arguments, output: &mut W) -> Result<(), String> {
+    let config = solver_config(arguments)?;
+    let io_error = |e: std::io::Error| e.to_string();
+    for result in solve_pack(pack, &config) {
+        match &result.solution {
+            Some(solution) => {
+                let mut details = String::new();
+                if arguments.options.contains_key("--cost") {
+                    details += &format!(", cost {}", solution.cost.primary);
+                }
+                if config.one_hero_at_a_time {
+                    details += &format!(", {} switches", solution.switches_count());
+                }
+                if !solution.optimal {
+                    details += ", maybe not optimal";
+                }
+                if result.over_par() {
+                    details += ", over par";
+                }
+                writeln!(
+                    output,
+                    "{}: {} moves{}",
+                    result.level.name,
+                    solution.moves.len(),
+                    details
+                )
+                .map_err(io_error)?
+            }
+            None => writeln!(output, "{}: no solution", result.level.name).map_err(io_error)?,
+        }
+    }
+    Ok(())
+}
+
+fn batch<W: Write>(directory: &str, arguments: &Arguments, output: &mut W) -> Result<(), String> {
+    let config = SolverConfig {
+        limits: Limits {
+            max_expanded: arguments.number("--max-states")?,
+            max_time: arguments
+                .number("--max-seconds")?
+                .map(Duration::from_secs_f64),
+        },
+        ..solver_config(arguments)?
+    };
+    let reports = solve_directory(Path::new(directory), &config)
+        .map_err(|e| format!("{}: {}", directory, e))?;
+    let mut report = vec![];
+    if arguments.has_flag("--json") {
+        write_json(&reports, &mut report)
+    } else {
+        write_csv(&reports, &mut report)
+    }
+    .map_err(|e| e.to_string())?;
+    write_output(&report, arguments, output)
+}
+
+fn write_output<W: Write>(
+    text: &[u8],
+    arguments: &Arguments,
+    output: &mut W,
+) -> Result<(), String> {
+    match arguments.options.get("--output") {
+        Some(path) => std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e)),
+        None => output.write_all(text).map_err(|e| e.to_string()),
+    }
+}
+
+fn parse_size(size: &str) -> Result<(usize, usize), String> {
+    let error = || format!("--size needs <width>x<height>, not {}", size);
+    let (width, height) = size.split_once('x').ok_or_else(error)?;
+    Ok((
+        width.parse().map_err(|_| error())?,
+        height.parse().map_err(|_| error())?,
+    ))
+}
+
+fn import<W: Write>(dump: &str, arguments: &Arguments, output: &mut W) -> Result<(), String> {
+    let path = arguments
+        .options
+        .get("--tiles")
+        .ok_or("import needs --tiles <file>, the blocks of the tile ids")?;
+    let tiles = std::fs::read_to_string(path)
+        .and_then(|text| TileMap::parse(&text))
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let with_path = |message: String| format!("{}: {}", dump, message);
+    let stages = match arguments.options.get("--size") {
+        Some(size) => {
+            let bytes = std::fs::read(dump).map_err(|e| with_path(e.to_string()))?;
+            stages_from_bytes(&bytes, parse_size(size)?, &tiles).map_err(with_path)?
+        }
+        None => std::fs::read_to_string(dump)
+            .and_then(|text| parse_dump(&text, &tiles))
+            .map_err(|e| with_path(e.to_string()))?,
+    };
+    let name = Path::new(dump)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    write_output(
+        to_pack(&stages, &name).to_text().as_bytes(),
+        arguments,
+        output,
+    )
+}
+
+// THE SIZE OF THE STAGE OF A NEW LEVEL
+const NEW_LEVEL_SIZE: (usize, usize) = (10, 8);
+
+fn edit_level<R: BufRead, W: Write>(
+    path: &str,
+    name: Option<&&str>,
+    input: R,
+    output: &mut W,
+    style: &Style,
+) -> Result<(), String> {
+    let pack = if Path::new(path).exists() {
+        Some(load_pack(path)?)
+    } else {
+        None
+    };
+    let levels = pack.as_ref().map(|p| p.levels.as_slice()).unwrap_or(&[]);
+    let existing = match name {
+        Some(name) => levels.iter().find(|l| l.name == **name),
+        None => levels.first(),
+    };
+    let mut editor = match existing {
+        Some(level) => Editor::from_level(level),
+        None => {
+            let name = name
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("Level {}", levels.len() + 1));
+            let (width, height) = NEW_LEVEL_SIZE;
+            Editor::new(&name, width, height)
+        }
+    };
+    edit(&mut editor, Some(path), input, output, style).map_err(|e| e.to_string())
+}
+
+// RUNS THE COMMAND IN args (WITHOUT THE NAME OF THE PROGRAM). play READS THE MOVES
+// FROM input
+pub fn run<R: BufRead, W: Write>(
+    args: &[String],
+    input: R,
+    output: &mut W,
+    style: &Style,
+) -> Result<(), String> {
+    let arguments = parse_arguments(args)?;
+    let positional = &arguments.positional;
+    let io_error = |e: std::io::Error| e.to_string();
+    match positional.as_slice() {
+        ["list", pack] => {
+            let pack = load_pack(pack)?;
+            let progress = load_progress(arguments.save())?;
+            list(&pack, &progress, output).map_err(io_error)
+        }
+        ["solve", pack] => solve(&load_pack(pack)?, &arguments, output),
+        ["batch", directory] => batch(directory, &arguments, output),
+        ["import", dump] => import(dump, &arguments, output),
+        ["edit", pack, rest @ ..] if rest.len() <= 1 => {
+            edit_level(pack, rest.first(), input, output, style)
+        }
+        ["play", pack, rest @ ..] if rest.len() <= 1 => {
+            let pack = load_pack(pack)?;
+            let mut progress = load_progress(arguments.save())?;
+            let level = match rest.first() {
+                Some(name) => pack
+                    .level(name)
+                    .ok_or(format!("no level {} in {}", name, pack.name))?,
+                None => pack
+                    .levels
+                    .iter()
+                    .find(|l| !progress.is_solved(&pack.name, &l.name))
+                    .or(pack.levels.first())
+                    .ok_or(format!("{} has no levels", pack.name))?,
+            };
+            let config = solver_config(&arguments)?;
+            let outcome = play(level, &config, input, output, style).map_err(io_error)?;
+            if outcome.solved && progress.record(&pack.name, &level.name, outcome.moves) {
+                writeln!(output, "new best for {}", level.name).map_err(io_error)?;
+                progress
+                    .save(Path::new(arguments.save()))
+                    .map_err(|e| format!("{}: {}", arguments.save(), e))?;
+            }
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::terminal::Style;
+
+    const PACK: &str = "pack: Easy
+# Only right
+par: 2
+WWWWWWWWW
+W@ G    W
+WWWWWWWWW
+# Two ghosts
+WWWWWWWWW
+W@ G    W
+WWWW    W
+WG      W
+WWWWWWWWW
+";
+
+    fn run_with(args: &[&str], input: &str) -> Result<String, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut output = vec![];
+        run(&args, input.as_bytes(), &mut output, &Style::plain())?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn play_and_list() {
+        let directory = std::env::temp_dir().join(format!("catrap-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let pack = directory.join("easy.txt");
+        std::fs::write(&pack, PACK).unwrap();
+        let save = directory.join("progress.save");
+        let (pack, save) = (pack.to_str().unwrap(), save.to_str().unwrap());
+
+        let listed = run_with(&["list", pack, "--save", save], "").unwrap();
+        assert!(listed.starts_with("Easy: 0 of 2 solved"));
+
+        let played = run_with(&["play", pack, "--save", save], "r\nr\n").unwrap();
+        assert!(played.contains("new best for Only right"));
+        let listed = run_with(&["list", pack, "--save", save], "").unwrap();
+        assert!(listed.starts_with("Easy: 1 of 2 solved"));
+        assert!(listed.contains("best 2"));
+
+        // THE FIRST UNSOLVED LEVEL IS PLAYED BY DEFAULT
+        let played = run_with(&["play", pack, "--save", save], "q\n").unwrap();
+        assert!(played.contains("Two ghosts  moves: 0"));
+
+        let solved = run_with(&["solve", pack], "").unwrap();
+        assert_eq!(solved, "Only right: 2 moves\nTwo ghosts: 6 moves\n");
+        let solved = run_with(&["solve", pack, "--cost", "sand", "--one-hero"], "").unwrap();
+        assert_eq!(
+            solved,
+            "Only right: 2 moves, cost 0, 0 switches\nTwo ghosts: 6 moves, cost 0, 0 switches\n"
+        );
+        let solved = run_with(&["solve", pack, "--algorithm", "ida:100"], "").unwrap();
+        assert_eq!(solved, "Only right: 2 moves\nTwo ghosts: 6 moves\n");
+        let solved = run_with(&["solve", pack, "--algorithm", "staged"], "").unwrap();
+        assert_eq!(
+            solved,
+            "Only right: 2 moves\nTwo ghosts: 6 moves, maybe not optimal\n"
+        );
+        assert!(run_with(&["solve", pack, "--algorithm", "ida:all"], "").is_err());
+        assert!(run_with(&["solve", pack, "--algorithm", "astar:100"], "").is_err());
+        assert!(run_with(&["solve", pack, "--cost", "time"], "")
+            .unwrap_err()
+            .starts_with("unknown cost model time"));
+
+        std::fs::remove_file(save).unwrap();
+        let directory = directory.to_str().unwrap();
+        let report = run_with(&["batch", directory, "--max-states", "2"], "").unwrap();
+        let rows: Vec<&str> = report.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            "file,level,solved,limit_reached,moves,expanded,time_ms,error"
+        );
+        assert!(rows[2].starts_with("easy.txt,Two ghosts,false,true,,2,"));
+        // NO ERROR
+        assert!(rows[2].ends_with(','));
+        let report = run_with(&["batch", directory, "--json"], "").unwrap();
+        assert!(report.contains("\"moves\": 6"));
+
+        let dump = format!("{}/stages.dump", directory);
+        std::fs::write(&dump, "stage 7\n01 01 01 01\n01 07 05 01\n01 01 01 01\n").unwrap();
+        assert!(run_with(&["import", &dump], "").is_err());
+        let tiles = format!("{}/tiles.txt", directory);
+        std::fs::write(&tiles, "00  \n01 W\n05 G\n07 @\n").unwrap();
+        let imported = run_with(&["import", &dump, "--tiles", &tiles], "").unwrap();
+        assert_eq!(imported, "pack: stages\n# Stage 7\nWWWW\nW@GW\nWWWW\n");
+
+        let edited = run_with(&["edit", pack, "Only right"], "at 6 1\np G\nsave\nq\n").unwrap();
+        assert!(edited.contains("saved in"));
+        let solved = run_with(&["solve", pack], "").unwrap();
+        assert!(solved.starts_with("Only right: 5 moves, over par\n"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn usage() {
+        assert!(run_with(&[], "").unwrap_err().starts_with("usage"));
+        assert!(run_with(&["play"], "").is_err());
+        assert!(run_with(&["list", "/does/not/exist.txt"], "").is_err());
+        assert!(run_with(&["solve", "pack.txt", "--unknown"], "").is_err());
+        assert!(run_with(&["batch", ".", "--max-states", "many"], "").is_err());
+        assert!(run_with(&["import", "stages.bin", "--size", "10"], "").is_err());
+    }
+}
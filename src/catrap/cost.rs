@@ -0,0 +1,110 @@
+use std::fmt::Debug;
+use std::ops::Add;
+
+use pathfinding::num_traits::Zero;
+
+use super::block::Block;
+use super::moves::Move;
+use super::state::State;
+
+// THE COST MODEL DECIDES THE PRIMARY COST, TIES ARE BROKEN BY THE NUMBER OF MOVES
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Cost {
+    pub primary: usize,
+    pub moves: usize,
+}
+
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost {
+            primary: self.primary + other.primary,
+            moves: self.moves + other.moves,
+        }
+    }
+}
+
+impl Zero for Cost {
+    fn zero() -> Self {
+        Cost::default()
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Cost::default()
+    }
+}
+
+pub trait CostModel: Debug {
+    // RETURNS: the primary cost of doing a_move in from, that leads to to
+    fn primary_cost(&self, from: &State, a_move: &Move, to: &State) -> usize;
+
+    fn cost(&self, from: &State, a_move: &Move, to: &State) -> Cost {
+        Cost {
+            primary: self.primary_cost(from, a_move, to),
+            moves: 1,
+        }
+    }
+}
+
+// RETURNS: the block a hero step goes into, before the step is done
+fn target_block(from: &State, a_move: &Move) -> Option<Block> {
+    match a_move {
+        Move::Step(hero_index, direction) => {
+            let hero = from.heroes()[*hero_index];
+            Some(from.block_at(&direction.move_point(&hero)))
+        }
+        Move::Switch(_) => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct MoveCount;
+
+impl CostModel for MoveCount {
+    fn primary_cost(&self, _from: &State, a_move: &Move, _to: &State) -> usize {
+        if a_move.is_switch() { 0 } else { 1 }
+    }
+}
+
+#[derive(Debug)]
+pub struct MovesAndSwitches;
+
+impl CostModel for MovesAndSwitches {
+    fn primary_cost(&self, _from: &State, _a_move: &Move, _to: &State) -> usize {
+        1
+    }
+}
+
+#[derive(Debug)]
+pub struct SandDug;
+
+impl CostModel for SandDug {
+    fn primary_cost(&self, from: &State, a_move: &Move, _to: &State) -> usize {
+        match target_block(from, a_move) {
+            Some(Block::SandWall) => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Pushes;
+
+impl CostModel for Pushes {
+    fn primary_cost(&self, from: &State, a_move: &Move, _to: &State) -> usize {
+        match target_block(from, a_move) {
+            Some(Block::Rock) => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Switches;
+
+impl CostModel for Switches {
+    fn primary_cost(&self, _from: &State, a_move: &Move, _to: &State) -> usize {
+        if a_move.is_switch() { 1 } else { 0 }
+    }
+}
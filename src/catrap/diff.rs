@@ -0,0 +1,265 @@
+use std::io::Write;
+
+use super::block::Block;
+use super::stage::Point;
+use super::state::State;
+use crate::terminal::{self, Colour, Glyph, Style};
+
+// SPACE BETWEEN THE STATES PRINTED SIDE BY SIDE
+const GUTTER: &str = "   ";
+// NUMBER OF KINDS OF KEYS
+const KEY_KINDS: u8 = 4;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CellChange {
+    pub location: Point,
+    pub before: Block,
+    pub after: Block,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HeroMovement {
+    pub hero: usize,
+    pub from: Point,
+    pub to: Point,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StateDiff {
+    // THE BLOCKS OF THE STAGE THAT CHANGED, WITHOUT THE HEROES
+    pub cells: Vec<CellChange>,
+    pub heroes: Vec<HeroMovement>,
+    pub ghosts_delta: isize,
+    pub keys_collected: Vec<u8>,
+}
+
+impl StateDiff {
+    pub fn between(before: &State, after: &State) -> StateDiff {
+        let (width, height) = (
+            before.width().max(after.width()),
+            before.height().max(after.height()),
+        );
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x as i32, y as i32)))
+            .filter_map(|location| {
+                let (b, a) = (
+                    before.stage_block_at(&location),
+                    after.stage_block_at(&location),
+                );
+                if b != a {
+                    Some(CellChange {
+                        location,
+                        before: b,
+                        after: a,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let heroes = before
+            .heroes()
+            .iter()
+            .zip(after.heroes())
+            .enumerate()
+            .filter(|(_, (from, to))| from != to)
+            .map(|(hero, (from, to))| HeroMovement {
+                hero,
+                from: *from,
+                to: *to,
+            })
+            .collect();
+        let keys_collected = (0..KEY_KINDS)
+            .filter(|kind| !before.has_key(*kind) && after.has_key(*kind))
+            .collect();
+        StateDiff {
+            cells,
+            heroes,
+            ghosts_delta: after.ghosts_count() as isize - before.ghosts_count() as isize,
+            keys_collected,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.heroes.is_empty() && self.keys_collected.is_empty()
+    }
+
+    // RETURNS: true if what is drawn at location changed, a block or a hero
+    pub fn changes_at(&self, location: &Point) -> bool {
+        self.cells.iter().any(|c| c.location == *location)
+            || self
+                .heroes
+                .iter()
+                .any(|h| h.from == *location || h.to == *location)
+    }
+}
+
+fn glyph_at(state: &State, location: &Point) -> Glyph {
+    if state.heroes().contains(location) {
+        Block::Hero.glyph()
+    } else {
+        state.block_at(location).glyph()
+    }
+}
+
+// PRINTS BOTH STATES SIDE BY SIDE WITH THE CHANGED CELLS HIGHLIGHTED, AND THE
+// CHANGES BELOW THEM. WITHOUT COLOURS, A THIRD GRID MARKS THE CHANGED CELLS
+pub fn write_diff<W: Write>(
+    before: &State,
+    after: &State,
+    output: &mut W,
+    style: &Style,
+) -> std::io::Result<()> {
+    let diff = StateDiff::between(before, after);
+    let (bw, bh) = before.drawn_size();
+    let (aw, ah) = after.drawn_size();
+    let (width, height) = (bw.max(aw), bh.max(ah));
+    let marker = Glyph::new('^', '▲', Some(Colour::Red));
+    let unchanged = Glyph::new('.', '·', None);
+
+    for line in 0..height {
+        for column in 0..width {
+            terminal::write_glyph(
+                output,
+                &glyph_at(before, &(column as i32, line as i32)),
+                style,
+            )?;
+        }
+        write!(output, "{}", GUTTER)?;
+        for column in 0..width {
+            let location = (column as i32, line as i32);
+            let glyph = glyph_at(after, &location);
+            if diff.changes_at(&location) {
+                terminal::write_highlighted_glyph(output, &glyph, style)?;
+            } else {
+                terminal::write_glyph(output, &glyph, style)?;
+            }
+        }
+        if !style.colour {
+            write!(output, "{}", GUTTER)?;
+            for column in 0..width {
+                let changed = diff.changes_at(&(column as i32, line as i32));
+                terminal::write_glyph(output, if changed { &marker } else { &unchanged }, style)?;
+            }
+        }
+        writeln!(output)?;
+    }
+
+    if diff.is_empty() {
+        writeln!(output, "nothing changed")?;
+    }
+    for change in diff.cells.iter() {
+        writeln!(
+            output,
+            "{:?}: '{}' -> '{}'",
+            change.location,
+            change.before.to_char(),
+            change.after.to_char()
+        )?;
+    }
+    for movement in diff.heroes.iter() {
+        writeln!(
+            output,
+            "hero {}: {:?} -> {:?}",
+            movement.hero, movement.from, movement.to
+        )?;
+    }
+    if diff.ghosts_delta != 0 {
+        writeln!(output, "ghosts: {:+}", diff.ghosts_delta)?;
+    }
+    for kind in diff.keys_collected.iter() {
+        writeln!(output, "key collected: '{}'", Block::Key(*kind).to_char())?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::block::Block;
+    use super::super::direction::Direction;
+    use super::super::state::State;
+    use super::{write_diff, CellChange, HeroMovement, StateDiff};
+    use crate::terminal::Style;
+
+    #[test]
+    fn push_and_fall() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWW",
+            "W@R  W",
+            "WW   W",
+            "WWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let next = state.next_state(0, Direction::Right).unwrap();
+        let diff = StateDiff::between(&state, &next);
+        assert_eq!(
+            diff.cells,
+            vec![
+                CellChange {
+                    location: (2, 1),
+                    before: Block::Rock,
+                    after: Block::Empty
+                },
+                CellChange {
+                    location: (3, 2),
+                    before: Block::Empty,
+                    after: Block::Rock
+                },
+            ]
+        );
+        assert!(diff.heroes.is_empty());
+        assert_eq!(diff.ghosts_delta, 0);
+        assert!(StateDiff::between(&next, &next).is_empty());
+
+        let mut output = vec![];
+        write_diff(&state, &next, &mut output, &Style::plain()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        output.lines().for_each(|l| println!("{}", l));
+        assert_eq!(output.lines().nth(1).unwrap(), "W@R  W   W@   W   ..^...");
+        assert_eq!(output.lines().nth(2).unwrap(), "WW   W   WW R W   ...^..");
+        assert!(output.contains("(2, 1): 'R' -> ' '"));
+        assert!(!output.contains("nothing changed"));
+
+        let mut output = vec![];
+        write_diff(&next, &next, &mut output, &Style::plain()).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("nothing changed"));
+    }
+
+    #[test]
+    fn kill_ghost_and_collect_key() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWW",
+            "W@Ga W",
+            "WWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let killed = state.next_state(0, Direction::Right).unwrap();
+        let diff = StateDiff::between(&state, &killed);
+        assert_eq!(diff.ghosts_delta, -1);
+        assert_eq!(
+            diff.heroes,
+            vec![HeroMovement {
+                hero: 0,
+                from: (1, 1),
+                to: (2, 1)
+            }]
+        );
+
+        let collected = killed.next_state(0, Direction::Right).unwrap();
+        let diff = StateDiff::between(&killed, &collected);
+        assert_eq!(diff.keys_collected, vec![0]);
+        assert_eq!(diff.ghosts_delta, 0);
+
+        let colour = Style {
+            unicode: true,
+            colour: true,
+        };
+        let mut output = vec![];
+        write_diff(&state, &killed, &mut output, &colour).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[7m"));
+        assert!(output.contains("ghosts: -1"));
+    }
+}
@@ -0,0 +1,155 @@
+use super::exploration::explore;
+use super::solver::{solve, SolverConfig};
+use super::state::State;
+
+// WEIGHTS OF EACH METRIC IN THE SCORE
+const SOLUTION_LENGTH_WEIGHT: f64 = 1.0;
+const BRANCHING_FACTOR_WEIGHT: f64 = 2.0;
+const IRREVERSIBLE_MOVE_WEIGHT: f64 = 2.0;
+const EXPANDED_STATES_WEIGHT: f64 = 3.0;
+const DEAD_ENDS_WEIGHT: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Tier {
+    Trivial,
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Tier {
+    fn from_score(score: f64) -> Tier {
+        match score {
+            s if s < 15.0 => Tier::Trivial,
+            s if s < 30.0 => Tier::Easy,
+            s if s < 50.0 => Tier::Medium,
+            s if s < 80.0 => Tier::Hard,
+            _ => Tier::Expert,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tier::Trivial => "trivial",
+            Tier::Easy => "easy",
+            Tier::Medium => "medium",
+            Tier::Hard => "hard",
+            Tier::Expert => "expert",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Difficulty {
+    pub solution_length: usize,
+    pub branching_factor: f64,
+    pub irreversible_moves_in_solution: usize,
+    pub states_expanded: usize,
+    pub dead_ends: usize,
+    pub score: f64,
+    pub tier: Tier,
+}
+
+// RETURNS: the difficulty of a level, or None if it can not be solved. At most
+// exploration_limit states are explored to count dead ends and irreversible moves
+pub fn rate_difficulty(
+    state: &State,
+    config: &SolverConfig,
+    exploration_limit: usize,
+) -> Option<Difficulty> {
+    let solution = solve(state, config)?;
+    let report = explore(state, config, exploration_limit);
+
+    let irreversible_moves_in_solution = solution
+        .states
+        .iter()
+        .zip(solution.moves.iter())
+        .filter(|(st, m)| {
+            report
+                .irreversible_moves
+                .iter()
+                .any(|(s, i)| s == *st && i == *m)
+        })
+        .count();
+    let solution_length = solution.moves.len();
+    let branching_factor = report.branching_factor();
+    let states_expanded = solution.expanded;
+    let dead_ends = report.dead_ends.len();
+
+    // COUNTS THAT GROW EXPONENTIALLY ARE SCORED BY THEIR LOGARITHM
+    let score = SOLUTION_LENGTH_WEIGHT * solution_length as f64
+        + BRANCHING_FACTOR_WEIGHT * branching_factor
+        + IRREVERSIBLE_MOVE_WEIGHT * irreversible_moves_in_solution as f64
+        + EXPANDED_STATES_WEIGHT * (1.0 + states_expanded as f64).ln()
+        + DEAD_ENDS_WEIGHT * (1.0 + dead_ends as f64).ln();
+
+    Some(Difficulty {
+        solution_length,
+        branching_factor,
+        irreversible_moves_in_solution,
+        states_expanded,
+        dead_ends,
+        score,
+        tier: Tier::from_score(score),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solver::SolverConfig;
+    use super::super::state::State;
+    use super::{rate_difficulty, Tier};
+
+    #[test]
+    fn trivial_level() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@G     W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let difficulty = rate_difficulty(&state, &SolverConfig::default(), 1000).unwrap();
+        assert_eq!(difficulty.solution_length, 1);
+        assert_eq!(difficulty.irreversible_moves_in_solution, 1);
+        assert_eq!(difficulty.tier, Tier::Trivial);
+    }
+
+    #[test]
+    fn harder_levels_score_higher() {
+        #[rustfmt::skip]
+            let easy = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWWWWWWW",
+        ];
+        #[rustfmt::skip]
+            let hard = vec![
+            "WWWWWWWWW",
+            "W@  *  GW",
+            "WWW=WWWWW",
+            "W  = G  W",
+            "WWWWWW WW",
+            "WWWWWWWWW",
+        ];
+        let config = SolverConfig::default();
+        let easy = rate_difficulty(&State::from_lines(easy), &config, 10000).unwrap();
+        let hard = rate_difficulty(&State::from_lines(hard), &config, 10000).unwrap();
+        assert!(hard.score > easy.score);
+        assert!(hard.tier >= easy.tier);
+        assert!(hard.dead_ends > 0);
+    }
+
+    #[test]
+    fn unsolvable_level() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@W G   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert!(rate_difficulty(&state, &SolverConfig::default(), 1000).is_none());
+    }
+}
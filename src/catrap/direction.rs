@@ -1,7 +1,7 @@
 use std::slice::Iter;
 use super::stage::Point;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Direction {
     Up,
     Down,
@@ -20,15 +20,21 @@ impl Direction {
         }
     }
 
-    pub fn is_horizontal(&self) -> bool{
+    pub fn opposite(&self) -> Direction {
         use Direction::*;
-        match self{
-            Up => false,
-            Down=> false,
-            _ => true
+        match self {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
         }
     }
 
+    pub fn is_horizontal(&self) -> bool{
+        use Direction::*;
+        !matches!(self, Up | Down)
+    }
+
     pub fn iterator() -> Iter<'static, Direction> {
         use Direction::*;
         static DIRECTIONS: [Direction; 4] = [Up, Down, Left, Right];
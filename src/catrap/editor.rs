@@ -0,0 +1,503 @@
+// A LEVEL EDITOR FOR THE TERMINAL, ONE COMMAND PER LINE. THE LEVEL IS SAVED IN A PACK
+// FILE: THE LEVEL WITH THE SAME NAME IS REPLACED, OR THE LEVEL IS ADDED AT THE END
+
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use super::block::Block;
+use super::direction::Direction;
+use super::pack::{Pack, PackLevel};
+use super::play::play;
+use super::solver::{search, Limits, SolverConfig};
+use super::stage::{Point, Stage, HEIGHT, WIDTH};
+use super::state::State;
+use crate::terminal::{self, Glyph, Style};
+
+const HELP: &str = "commands:
+  l|r|u|d [n]        moves the cursor n places, 1 by default
+  at <x> <y>         moves the cursor to a place
+  p [<block>]        puts a block (a character of the level files, @ for a hero) at the
+                     cursor, empty by default
+  size <w> <h>       resizes the stage, up to 12 x 12
+  name <name>        renames the level
+  par [<moves>]      sets the par of the level, removes it without moves
+  play               plays the level until it is solved or q
+  solve              runs the solver
+  dump               prints the level as the dump routines of the game do
+  save [<file>]      saves the level in a pack file, the one it was opened from by default
+  q                  quits";
+
+// STATES EXPANDED BY solve BEFORE GIVING UP
+const SOLVE_LIMIT: usize = 1_000_000;
+
+pub struct Editor {
+    stage: Stage,
+    heroes: Vec<Point>,
+    width: usize,
+    height: usize,
+    cursor: Point,
+    pub name: String,
+    pub par: Option<usize>,
+}
+
+impl Editor {
+    // A STAGE OF width x height SURROUNDED BY WALLS
+    pub fn new(name: &str, width: usize, height: usize) -> Editor {
+        let mut lines = vec![];
+        for y in 0..height {
+            let line: String = (0..width)
+                .map(|x| {
+                    let border = x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+                    if border {
+                        'W'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            lines.push(line);
+        }
+        Editor::from_level(&PackLevel {
+            name: name.to_string(),
+            par: None,
+            lines,
+        })
+    }
+
+    pub fn from_level(level: &PackLevel) -> Editor {
+        let mut editor = Editor {
+            stage: Stage {
+                blocks: [[Block::Wall; WIDTH]; HEIGHT],
+            },
+            heroes: vec![],
+            width: level
+                .lines
+                .iter()
+                .map(|l| l.chars().count().min(WIDTH))
+                .max()
+                .unwrap_or(0),
+            height: level.lines.len().min(HEIGHT),
+            cursor: (0, 0),
+            name: level.name.clone(),
+            par: level.par,
+        };
+        // WHAT DOES NOT FIT IN THE STAGE IS LEFT OUT
+        for (y, line) in level.lines.iter().take(HEIGHT).enumerate() {
+            for (x, c) in line.chars().take(WIDTH).enumerate() {
+                editor.put(&(x as i32, y as i32), Block::from_char(c));
+            }
+        }
+        editor
+    }
+
+    #[cfg(test)]
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    #[cfg(test)]
+    pub fn cursor(&self) -> Point {
+        self.cursor
+    }
+
+    fn inside(&self, (x, y): &Point) -> bool {
+        *x >= 0 && *y >= 0 && (*x as usize) < self.width && (*y as usize) < self.height
+    }
+
+    // RETURNS: false if the cursor would leave the stage, and then it does not move
+    pub fn move_cursor(&mut self, direction: Direction, places: usize) -> bool {
+        let (dx, dy) = direction.move_point(&(0, 0));
+        let shift = |from: i32, delta: i32| {
+            let places = i64::try_from(places).ok()?;
+            let to = places.checked_mul(delta.into())?.checked_add(from.into())?;
+            i32::try_from(to).ok()
+        };
+        match (shift(self.cursor.0, dx), shift(self.cursor.1, dy)) {
+            (Some(x), Some(y)) => self.move_cursor_to(&(x, y)),
+            _ => false,
+        }
+    }
+
+    pub fn move_cursor_to(&mut self, location: &Point) -> bool {
+        if self.inside(location) {
+            self.cursor = *location;
+        }
+        self.cursor == *location
+    }
+
+    // A HERO IS PUT ON AN EMPTY PLACE
+    pub fn put(&mut self, location: &Point, block: Block) {
+        self.heroes.retain(|h| h != location);
+        if Block::is_hero(block) {
+            self.heroes.push(*location);
+            self.stage.set_block_at(location, Block::Empty);
+        } else {
+            self.stage.set_block_at(location, block);
+        }
+    }
+
+    pub fn put_at_cursor(&mut self, block: Block) {
+        self.put(&self.cursor.clone(), block);
+    }
+
+    // THE PLACES LEFT OUTSIDE BECOME WALLS, THE NEW ONES ARE EMPTY
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), String> {
+        if width == 0 || height == 0 || width > WIDTH || height > HEIGHT {
+            return Err(format!(
+                "the size must be from 1 x 1 to {} x {}",
+                WIDTH, HEIGHT
+            ));
+        }
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let location = (x as i32, y as i32);
+                let was_inside = self.inside(&location);
+                let is_inside = x < width && y < height;
+                if was_inside && !is_inside {
+                    self.put(&location, Block::Wall);
+                } else if !was_inside && is_inside {
+                    self.put(&location, Block::Empty);
+                }
+            }
+        }
+        self.width = width;
+        self.height = height;
+        let (x, y) = self.cursor;
+        self.cursor = (x.min(width as i32 - 1), y.min(height as i32 - 1));
+        Ok(())
+    }
+
+    fn char_at(&self, location: &Point) -> char {
+        if self.heroes.contains(location) {
+            Block::Hero.to_char()
+        } else {
+            self.stage.block_at(location).to_char()
+        }
+    }
+
+    // RETURNS: the lines of the level, with every place of the stage
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| self.char_at(&(x as i32, y as i32)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn state(&self) -> State {
+        State::from_lines(self.lines().iter().map(|l| l.as_str()).collect())
+    }
+
+    pub fn level(&self) -> PackLevel {
+        PackLevel {
+            name: self.name.clone(),
+            par: self.par,
+            lines: self.lines(),
+        }
+    }
+
+    // THE STAGE WITH THE CURSOR HIGHLIGHTED, AND WHAT IS UNDER THE CURSOR
+    pub fn render<W: Write>(&self, output: &mut W, style: &Style) -> std::io::Result<()> {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let location = (x as i32, y as i32);
+                let glyph = Block::from_char(self.char_at(&location)).glyph();
+                if location == self.cursor {
+                    let glyph = if style.colour {
+                        glyph
+                    } else {
+                        Glyph::new('+', '+', None)
+                    };
+                    terminal::write_highlighted_glyph(output, &glyph, style)?;
+                } else {
+                    terminal::write_glyph(output, &glyph, style)?;
+                }
+            }
+            writeln!(output)?;
+        }
+        writeln!(
+            output,
+            "{} ({} x {})  cursor {:?}: '{}'",
+            self.name,
+            self.width,
+            self.height,
+            self.cursor,
+            self.char_at(&self.cursor)
+        )
+    }
+
+    // RETURNS: the number of levels in the pack after saving
+    pub fn save(&self, path: &Path) -> std::io::Result<usize> {
+        let mut pack = if path.exists() {
+            Pack::load(path)?
+        } else {
+            Pack {
+                name: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                levels: vec![],
+            }
+        };
+        let level = self.level();
+        match pack.levels.iter_mut().find(|l| l.name == level.name) {
+            Some(existing) => *existing = level,
+            None => pack.levels.push(level),
+        }
+        pack.save(path)?;
+        Ok(pack.levels.len())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(text: Option<&str>) -> Result<T, String> {
+    let text = text.ok_or("a number is missing")?;
+    text.parse()
+        .map_err(|_| format!("{} is not a number", text))
+}
+
+fn solve<W: Write>(editor: &Editor, output: &mut W) -> std::io::Result<()> {
+    let config = SolverConfig {
+        limits: Limits {
+            max_expanded: Some(SOLVE_LIMIT),
+            max_time: None,
+        },
+        ..Default::default()
+    };
+    let outcome = search(&editor.state(), &config);
+    match outcome.solution {
+        Some(solution) => {
+            let moves: Vec<String> = solution.moves.iter().map(|m| format!("{:?}", m)).collect();
+            writeln!(
+                output,
+                "solved in {} moves, {} states expanded: {}",
+                moves.len(),
+                outcome.expanded,
+                moves.join(" ")
+            )
+        }
+        None if outcome.limit_reached => {
+            writeln!(output, "no solution found in {} states", outcome.expanded)
+        }
+        None => writeln!(output, "no solution"),
+    }
+}
+
+// RETURNS: an error if the level can not be played
+fn check_hero(editor: &Editor) -> Result<(), String> {
+    if editor.state().heroes_count() == 0 {
+        return Err("the level has no hero".to_string());
+    }
+    Ok(())
+}
+
+// RUNS ONE COMMAND. RETURNS: false to quit
+fn command<R: BufRead, W: Write>(
+    editor: &mut Editor,
+    line: &str,
+    file: &mut Option<String>,
+    input: &mut R,
+    output: &mut W,
+    style: &Style,
+) -> Result<bool, String> {
+    let io_error = |e: std::io::Error| e.to_string();
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let mut words = rest.split_whitespace();
+    match word {
+        "l" | "r" | "u" | "d" => {
+            let direction = match word {
+                "l" => Direction::Left,
+                "r" => Direction::Right,
+                "u" => Direction::Up,
+                _ => Direction::Down,
+            };
+            let places = match words.next() {
+                Some(places) => parse_number(Some(places))?,
+                None => 1,
+            };
+            if !editor.move_cursor(direction, places) {
+                return Err("the cursor would leave the stage".to_string());
+            }
+        }
+        "at" => {
+            let location = (parse_number(words.next())?, parse_number(words.next())?);
+            if !editor.move_cursor_to(&location) {
+                return Err(format!("{:?} is outside the stage", location));
+            }
+        }
+        "p" => {
+            // THE BLOCK MAY BE A SPACE
+            let mut chars = rest.chars();
+            let block = match (chars.next(), chars.next()) {
+                (None, _) => Some(Block::Empty),
+                (Some(c), None) => Block::try_from_char(c),
+                _ => None,
+            };
+            editor.put_at_cursor(block.ok_or(format!("{} is not a block", rest))?);
+        }
+        "size" => editor.resize(parse_number(words.next())?, parse_number(words.next())?)?,
+        "name" => match rest.trim() {
+            "" => return Err("name needs a name".to_string()),
+            name => editor.name = name.to_string(),
+        },
+        "par" => {
+            editor.par = match words.next() {
+                Some(par) => Some(parse_number(Some(par))?),
+                None => None,
+            }
+        }
+        "play" => {
+            check_hero(editor)?;
+            let config = SolverConfig::default();
+            play(&editor.level(), &config, input, output, style).map_err(io_error)?;
+        }
+        "solve" => {
+            check_hero(editor)?;
+            solve(editor, output).map_err(io_error)?;
+        }
+        "dump" => editor.state().dump(output).map_err(io_error)?,
+        "save" => {
+            let path = match rest.trim() {
+                "" => file.clone().ok_or("save needs a file")?,
+                path => path.to_string(),
+            };
+            let levels = editor
+                .save(Path::new(&path))
+                .map_err(|e| format!("{}: {}", path, e))?;
+            writeln!(output, "saved in {}, {} levels", path, levels).map_err(io_error)?;
+            *file = Some(path);
+        }
+        "q" | "quit" => return Ok(false),
+        _ => writeln!(output, "{}", HELP).map_err(io_error)?,
+    }
+    Ok(true)
+}
+
+// EDITS UNTIL q OR THE END OF input. save WITHOUT A FILE SAVES IN file
+pub fn edit<R: BufRead, W: Write>(
+    editor: &mut Editor,
+    file: Option<&str>,
+    mut input: R,
+    output: &mut W,
+    style: &Style,
+) -> std::io::Result<()> {
+    let mut file = file.map(|f| f.to_string());
+    editor.render(output, style)?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        match command(editor, line, &mut file, &mut input, output, style) {
+            Ok(false) => return Ok(()),
+            Ok(true) => {}
+            Err(message) => writeln!(output, "{}", message)?,
+        }
+        editor.render(output, style)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::block::Block;
+    use super::super::direction::Direction;
+    use super::super::pack::Pack;
+    use super::{edit, Editor};
+    use crate::terminal::Style;
+
+    fn edit_with(editor: &mut Editor, file: Option<&str>, input: &str) -> String {
+        let mut output = vec![];
+        edit(editor, file, input.as_bytes(), &mut output, &Style::plain()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn put_and_resize() {
+        let mut editor = Editor::new("new", 5, 3);
+        assert_eq!(editor.lines(), vec!["WWWWW", "W   W", "WWWWW"]);
+        assert!(!editor.move_cursor(Direction::Left, 1));
+        assert!(!editor.move_cursor(Direction::Right, usize::MAX));
+        assert!(!editor.move_cursor(Direction::Up, i32::MAX as usize + 1));
+        assert_eq!(editor.cursor(), (0, 0));
+        assert!(editor.move_cursor(Direction::Right, 1));
+        assert!(editor.move_cursor(Direction::Down, 1));
+        editor.put_at_cursor(Block::Hero);
+        editor.move_cursor(Direction::Right, 2);
+        editor.put_at_cursor(Block::Ghost);
+        assert_eq!(editor.lines(), vec!["WWWWW", "W@ GW", "WWWWW"]);
+        assert_eq!(editor.state().ghosts_count(), 1);
+
+        editor.resize(3, 4).unwrap();
+        assert_eq!(editor.lines(), vec!["WWW", "W@ ", "WWW", "   "]);
+        assert_eq!(editor.cursor(), (2, 1));
+        assert_eq!(editor.state().ghosts_count(), 0);
+        assert!(editor.resize(13, 2).is_err());
+
+        // A HERO REPLACED BY A BLOCK IS GONE
+        editor.move_cursor_to(&(1, 1));
+        editor.put_at_cursor(Block::Rock);
+        assert_eq!(editor.state().heroes_count(), 0);
+    }
+
+    #[test]
+    fn commands() {
+        let mut editor = Editor::new("Commands", 9, 3);
+        let output = edit_with(
+            &mut editor,
+            None,
+            "at 1 1\np @\nr 2\np G\nsolve\nx\nr 20\np z\nname\npar 2\nplay\nr\nr\nq\n",
+        );
+        assert_eq!(editor.lines()[1], "W@ G    W");
+        assert_eq!(editor.par, Some(2));
+        assert!(output.contains("solved in 2 moves"));
+        assert!(output.contains("commands:"));
+        assert!(output.contains("the cursor would leave the stage"));
+        assert!(output.contains("z is not a block"));
+        assert!(output.contains("name needs a name"));
+        assert_eq!(editor.name, "Commands");
+        // THE GAME ENDS WITH THE LEVEL SOLVED AND THE EDITOR QUITS AFTER IT
+        assert!(output.contains("solved in 2 moves\n"));
+        assert!(output.ends_with("'G'\n"));
+    }
+
+    #[test]
+    fn no_hero_and_dump() {
+        let mut editor = Editor::new("Empty", 5, 3);
+        let output = edit_with(&mut editor, None, "at 2 1\np G\nsolve\nplay\ndump\nq\n");
+        assert_eq!(output.matches("the level has no hero").count(), 2);
+        assert!(!output.contains("no solution"));
+        // THE DUMP HAS THE WHOLE STAGE, WALLS OUTSIDE, AND ENDS WITH TWO EMPTY LINES
+        assert!(output.contains("\nW G WWWWWWWW\n"));
+        assert!(output.contains("WWWWWWWWWWWW\n\n\n"));
+    }
+
+    #[test]
+    fn save_in_a_pack() {
+        let directory = std::env::temp_dir().join(format!("catrap-editor-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("levels.txt");
+        let file = path.to_str().unwrap();
+
+        let mut editor = Editor::new("First", 4, 3);
+        let output = edit_with(&mut editor, Some(file), "at 1 1\np @\nr\np G\nsave\nq\n");
+        assert!(output.contains("1 levels"));
+        let mut editor = Editor::new("Second", 4, 3);
+        edit_with(&mut editor, Some(file), "at 2 1\np @\nname First\nsave\n");
+
+        let pack = Pack::load(&path).unwrap();
+        assert_eq!(pack.name, "levels");
+        assert_eq!(pack.levels.len(), 1);
+        assert_eq!(pack.levels[0].lines, vec!["WWWW", "W @W", "WWWW"]);
+
+        let mut editor = Editor::from_level(&pack.levels[0]);
+        assert_eq!(editor.size(), (4, 3));
+        editor.name = "Second".to_string();
+        assert_eq!(editor.save(&path).unwrap(), 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use super::block::Block;
+use super::stage::Point;
+use super::state::State;
+
+// WHAT HAPPENS DURING A MOVE, IN ORDER
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    HeroMoved {
+        hero: usize,
+        from: Point,
+        to: Point,
+    },
+    SandDug(Point),
+    RockPushed {
+        from: Point,
+        to: Point,
+    },
+    GhostKilled(Point),
+    KeyCollected {
+        kind: u8,
+        at: Point,
+    },
+    DoorOpened(Point),
+    Teleported {
+        hero: usize,
+        from: Point,
+        to: Point,
+    },
+    FloorCrumbled(Point),
+    // A ROCK, A GHOST OR A HERO (block IS Hero) FALLS. EACH EVENT IS ONE PLACE DOWN
+    Fell {
+        block: Block,
+        from: Point,
+        to: Point,
+    },
+}
+
+impl Event {
+    pub fn is_fall(&self) -> bool {
+        matches!(self, Event::Fell { .. })
+    }
+}
+
+// THE EVENTS OF A MOVE, EACH ONE WITH THE STATE RIGHT AFTER IT
+#[derive(Clone, Default)]
+pub struct Trace {
+    pub steps: Vec<(Event, State)>,
+}
+
+impl Trace {
+    #[cfg(test)]
+    pub fn events(&self) -> Vec<Event> {
+        self.steps.iter().map(|(event, _)| *event).collect()
+    }
+
+    // RETURNS: the state after the move
+    #[cfg(test)]
+    pub fn last_state(&self) -> Option<&State> {
+        self.steps.last().map(|(_, state)| state)
+    }
+
+    // RETURNS: every block that fell, from where it started to where it stopped
+    #[cfg(test)]
+    pub fn falls(&self) -> Vec<Event> {
+        let mut falls: Vec<Event> = vec![];
+        for event in self.events() {
+            if let Event::Fell { block, from, to } = event {
+                // ONLY ONE THING CAN BE AT from, THE LAST ONE THAT FELL THERE
+                let previous = falls.iter_mut().rev().find(
+                    |f| matches!(f, Event::Fell { block: b, to: t, .. } if *b == block && *t == from),
+                );
+                match previous {
+                    Some(Event::Fell { to: end, .. }) => *end = to,
+                    _ => falls.push(event),
+                }
+            }
+        }
+        falls
+    }
+
+    // RETURNS: the states to show in an animation: one after the events that are not
+    // falls, and one after every place that something falls
+    pub fn frames(&self) -> Vec<State> {
+        let mut frames: Vec<State> = vec![];
+        for (index, (event, state)) in self.steps.iter().enumerate() {
+            let next_is_fall = self.steps.get(index + 1).map(|(e, _)| e.is_fall());
+            if event.is_fall() || next_is_fall != Some(false) {
+                frames.push(state.clone());
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::block::Block;
+    use super::super::direction::Direction;
+    use super::super::state::State;
+    use super::Event;
+
+    #[test]
+    fn push_and_cascade() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W  R    W",
+            "W  R    W",
+            "W @R    W",
+            "WWWW    W",
+            "W       W",
+            "W       W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let trace = state.next_state_traced(0, Direction::Right).unwrap();
+        assert!(*trace.last_state().unwrap() == state.next_state(0, Direction::Right).unwrap());
+        assert_eq!(
+            trace.events(),
+            vec![
+                Event::RockPushed {
+                    from: (3, 3),
+                    to: (4, 3)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (3, 2),
+                    to: (3, 3)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (3, 1),
+                    to: (3, 2)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (4, 3),
+                    to: (4, 4)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (4, 4),
+                    to: (4, 5)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (4, 5),
+                    to: (4, 6)
+                },
+            ]
+        );
+        assert_eq!(
+            trace.falls(),
+            vec![
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (3, 2),
+                    to: (3, 3)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (3, 1),
+                    to: (3, 2)
+                },
+                Event::Fell {
+                    block: Block::Rock,
+                    from: (4, 3),
+                    to: (4, 6)
+                },
+            ]
+        );
+        let frames = trace.frames();
+        assert_eq!(frames.len(), 1 + 2 + 3);
+        assert!(frames.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(matches!(frames[0].block_at(&(4, 3)), Block::Rock));
+    }
+
+    #[test]
+    fn kill_dig_and_fall() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWW",
+            "W@G* W",
+            "WWWW W",
+            "W    W",
+            "WWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let killed = state.next_state_traced(0, Direction::Right).unwrap();
+        assert_eq!(
+            killed.events(),
+            vec![
+                Event::GhostKilled((2, 1)),
+                Event::HeroMoved {
+                    hero: 0,
+                    from: (1, 1),
+                    to: (2, 1)
+                }
+            ]
+        );
+        assert_eq!(killed.frames().len(), 1);
+
+        let dug = killed
+            .last_state()
+            .unwrap()
+            .next_state_traced(0, Direction::Right)
+            .unwrap();
+        assert_eq!(
+            dug.events(),
+            vec![
+                Event::SandDug((3, 1)),
+                Event::HeroMoved {
+                    hero: 0,
+                    from: (2, 1),
+                    to: (3, 1)
+                }
+            ]
+        );
+
+        let fell = dug
+            .last_state()
+            .unwrap()
+            .next_state_traced(0, Direction::Right)
+            .unwrap();
+        assert_eq!(
+            fell.falls(),
+            vec![Event::Fell {
+                block: Block::Hero,
+                from: (4, 1),
+                to: (4, 3)
+            }]
+        );
+        assert_eq!(fell.frames().len(), 3);
+    }
+
+    #[test]
+    fn no_trace_without_move() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWW",
+            "W@WW",
+            "WWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert!(state.next_state_traced(0, Direction::Right).is_none());
+    }
+}
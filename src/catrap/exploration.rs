@@ -0,0 +1,225 @@
+use std::collections::{HashMap, VecDeque};
+
+use pathfinding::prelude::strongly_connected_components;
+
+use super::moves::Move;
+use super::solver::{initial_state, SolverConfig};
+use super::state::State;
+
+pub struct ExplorationReport {
+    pub states_count: usize,
+    pub solved_count: usize,
+    // NUMBER OF MOVES BETWEEN THE EXPLORED STATES
+    pub moves_count: usize,
+    // NUMBER OF STATES WHOSE SHORTEST PATH FROM THE START HAS THE INDEX AS LENGTH
+    pub depth_distribution: Vec<usize>,
+    // STATES FROM WHICH NO SOLVED STATE CAN BE REACHED
+    pub dead_ends: Vec<State>,
+    // MOVES AFTER WHICH THE PREVIOUS STATE CAN NOT BE REACHED AGAIN
+    pub irreversible_moves: Vec<(State, Move)>,
+    // THE LIMIT WAS REACHED: UNEXPLORED STATES ARE NOT COUNTED AS DEAD ENDS
+    pub truncated: bool,
+}
+
+impl ExplorationReport {
+    // RETURNS: the average number of moves of the explored states that are not solved
+    pub fn branching_factor(&self) -> f64 {
+        let unsolved = self.states_count - self.solved_count;
+        if unsolved == 0 {
+            0.0
+        } else {
+            self.moves_count as f64 / unsolved as f64
+        }
+    }
+}
+
+struct Node {
+    state: State,
+    depth: usize,
+    // ALL THE SUCCESSORS HAVE BEEN EXPLORED
+    complete: bool,
+    moves: Vec<(Move, usize)>,
+}
+
+// BREADTH FIRST SEARCH OF AT MOST limit STATES. SOLVED STATES ARE NOT EXPANDED
+#[allow(clippy::mutable_key_type)]
+fn explore_graph(start: &State, limit: usize) -> (Vec<Node>, bool) {
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        depth: 0,
+        complete: false,
+        moves: vec![],
+    }];
+    let mut indices: HashMap<State, usize> = HashMap::new();
+    indices.insert(start.clone(), 0);
+    let mut to_see = VecDeque::new();
+    to_see.push_back(0);
+    let mut truncated = false;
+
+    while let Some(index) = to_see.pop_front() {
+        if nodes[index].state.all_ghosts_gone() {
+            continue;
+        }
+        let mut moves = vec![];
+        let mut complete = true;
+        for (a_move, next) in nodes[index].state.reachable_moves() {
+            let next_index = match indices.get(&next) {
+                Some(n) => *n,
+                None if nodes.len() >= limit => {
+                    complete = false;
+                    continue;
+                }
+                None => {
+                    let n = nodes.len();
+                    indices.insert(next.clone(), n);
+                    nodes.push(Node {
+                        state: next,
+                        depth: nodes[index].depth + 1,
+                        complete: false,
+                        moves: vec![],
+                    });
+                    to_see.push_back(n);
+                    n
+                }
+            };
+            moves.push((a_move, next_index));
+        }
+        nodes[index].moves = moves;
+        nodes[index].complete = complete;
+        truncated = truncated || !complete;
+    }
+    (nodes, truncated)
+}
+
+// RETURNS: for each node, true if it can reach a solved state or a state not fully explored
+fn alive_nodes(nodes: &[Node]) -> Vec<bool> {
+    let mut predecessors: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    for (index, node) in nodes.iter().enumerate() {
+        for (_, next) in node.moves.iter() {
+            predecessors[*next].push(index);
+        }
+    }
+    let mut alive: Vec<bool> = nodes
+        .iter()
+        .map(|n| n.state.all_ghosts_gone() || !n.complete)
+        .collect();
+    let mut to_see: VecDeque<usize> = (0..nodes.len()).filter(|i| alive[*i]).collect();
+    while let Some(index) = to_see.pop_front() {
+        for previous in predecessors[index].iter() {
+            if !alive[*previous] {
+                alive[*previous] = true;
+                to_see.push_back(*previous);
+            }
+        }
+    }
+    alive
+}
+
+pub fn explore(state: &State, config: &SolverConfig, limit: usize) -> ExplorationReport {
+    let start = initial_state(state, config);
+    let (nodes, truncated) = explore_graph(&start, limit.max(1));
+
+    let mut depth_distribution = vec![];
+    for node in nodes.iter() {
+        if depth_distribution.len() <= node.depth {
+            depth_distribution.resize(node.depth + 1, 0);
+        }
+        depth_distribution[node.depth] += 1;
+    }
+
+    let alive = alive_nodes(&nodes);
+    let dead_ends = nodes
+        .iter()
+        .zip(alive.iter())
+        .filter(|(_, alive)| !**alive)
+        .map(|(node, _)| node.state.clone())
+        .collect();
+
+    let indices: Vec<usize> = (0..nodes.len()).collect();
+    let components = strongly_connected_components(&indices, |i| {
+        nodes[*i]
+            .moves
+            .iter()
+            .map(|(_, next)| *next)
+            .collect::<Vec<_>>()
+    });
+    let mut component_of = vec![0; nodes.len()];
+    for (c, component) in components.iter().enumerate() {
+        for index in component.iter() {
+            component_of[*index] = c;
+        }
+    }
+    let mut irreversible_moves = vec![];
+    for (index, node) in nodes.iter().enumerate() {
+        for (a_move, next) in node.moves.iter() {
+            if component_of[*next] != component_of[index] {
+                irreversible_moves.push((node.state.clone(), *a_move));
+            }
+        }
+    }
+
+    ExplorationReport {
+        states_count: nodes.len(),
+        solved_count: nodes.iter().filter(|n| n.state.all_ghosts_gone()).count(),
+        moves_count: nodes.iter().map(|n| n.moves.len()).sum(),
+        depth_distribution,
+        dead_ends,
+        irreversible_moves,
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::direction::Direction;
+    use super::super::moves::Move;
+    use super::super::solver::SolverConfig;
+    use super::super::state::State;
+    use super::explore;
+
+    #[test]
+    fn corridor() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@  G   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let report = explore(&state, &SolverConfig::default(), 1000);
+        assert!(!report.truncated);
+        assert_eq!(report.states_count, 4);
+        assert_eq!(report.solved_count, 1);
+        assert_eq!(report.moves_count, 5);
+        assert_eq!(report.depth_distribution, vec![1, 1, 1, 1]);
+        assert!(report.dead_ends.is_empty());
+        // ONLY KILLING THE GHOST CAN NOT BE UNDONE
+        assert_eq!(report.irreversible_moves.len(), 1);
+        assert_eq!(
+            report.irreversible_moves[0].1,
+            Move::Step(0, Direction::Right)
+        );
+    }
+
+    #[test]
+    fn dead_ends() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W G @   W",
+            "WWWWWW WW",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let report = explore(&state, &SolverConfig::default(), 1000);
+        report.dead_ends.iter().for_each(|s| s.dump_stdout());
+        // FALLING INTO THE HOLE CAN NOT BE UNDONE, AND THE GHOST IS NOT REACHABLE FROM THERE
+        assert_eq!(report.dead_ends.len(), 1);
+        assert_eq!(report.irreversible_moves.len(), 2);
+
+        let report = explore(&state, &SolverConfig::default(), 2);
+        assert!(report.truncated);
+        assert_eq!(report.states_count, 2);
+        assert!(report.dead_ends.is_empty());
+    }
+}
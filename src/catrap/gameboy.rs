@@ -0,0 +1,309 @@
+// IMPORTS THE STAGES OF THE ORIGINAL GAME BOY GAME FROM A DUMP OF ITS LEVEL DATA.
+//
+// THE DUMP IS A TEXT FILE WITH THE TILE IDS OF EACH STAGE, IN HEXADECIMAL, ONE LINE
+// OF TILES PER ROW OF THE STAGE:
+//
+//     ; STAGE DATA, 10 x 8 TILES EACH
+//     stage 1
+//     01 01 01 01 01 01 01 01 01 01
+//     01 07 00 05 00 00 00 00 00 01
+//     ...
+//     stage 2
+//     ...
+//
+// LINES STARTING WITH ';' AND EMPTY LINES ARE IGNORED. THE ROWS OF A STAGE MUST HAVE
+// THE SAME NUMBER OF TILES AND FIT IN THE 12 x 12 STAGE; EVERYTHING OUTSIDE IS WALL.
+// A RAW DUMP OF CONSECUTIVE STAGES, width x height BYTES EACH, ROW BY ROW, IS READ BY
+// stages_from_bytes.
+//
+// THE TILE IDS ARE TURNED INTO BLOCKS WITH A TileMap, WRITTEN AS LINES OF "id block",
+// E.G. "1f R", WITH THE CHARACTER OF THE BLOCK IN State::from_lines.
+//
+// ONLY PART OF THE IMPORT IS DONE: THE TABLE OF THE ORIGINAL CARTRIDGE IS NOT SHIPPED,
+// BECAUSE ITS TILE IDS HAVE NOT BEEN CHECKED AGAINST A REAL DUMP. UNTIL THEY ARE, EVERY
+// DUMP MUST COME WITH ITS OWN TABLE
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+use super::block::Block;
+use super::pack::{Pack, PackLevel};
+use super::stage::{HEIGHT, WIDTH};
+use super::state::State;
+
+const STAGE_PREFIX: &str = "stage";
+const COMMENT_PREFIX: &str = ";";
+
+fn invalid_data(line: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("line {}: {}", line + 1, message),
+    )
+}
+
+fn parse_id(text: &str) -> Option<u8> {
+    let text = text.trim_start_matches("0x");
+    u8::from_str_radix(text, 16).ok()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TileMap {
+    blocks: HashMap<u8, Block>,
+}
+
+impl TileMap {
+    // LINES OF "id block", THE ID IN HEXADECIMAL. LINES STARTING WITH ';' ARE IGNORED
+    pub fn parse(text: &str) -> std::io::Result<TileMap> {
+        let mut blocks = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            // THE BLOCK MAY BE A SPACE, SO ONLY THE FIRST SPACE SEPARATES
+            let (id, block) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid_data(number, "expected a tile id and a block"))?;
+            let id = parse_id(id)
+                .ok_or_else(|| invalid_data(number, "the tile id is not hexadecimal"))?;
+            let mut chars = block.chars();
+            let block = match (chars.next(), chars.next()) {
+                (Some(c), None) => Block::try_from_char(c),
+                _ => None,
+            }
+            .ok_or_else(|| invalid_data(number, "expected the character of a block"))?;
+            if blocks.insert(id, block).is_some() {
+                return Err(invalid_data(number, "the tile id is repeated"));
+            }
+        }
+        Ok(TileMap { blocks })
+    }
+
+    pub fn block(&self, id: u8) -> Option<Block> {
+        self.blocks.get(&id).copied()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportedStage {
+    pub number: usize,
+    // THE LINES OF State::from_lines
+    pub lines: Vec<String>,
+}
+
+impl ImportedStage {
+    #[allow(dead_code)]
+    pub fn state(&self) -> State {
+        State::from_lines(self.lines.iter().map(|l| l.as_str()).collect())
+    }
+}
+
+fn row_to_line(ids: &[u8], tiles: &TileMap) -> Result<String, String> {
+    ids.iter()
+        .map(|id| {
+            tiles
+                .block(*id)
+                .map(|b| b.to_char())
+                .ok_or(format!("unknown tile id {:02x}", id))
+        })
+        .collect()
+}
+
+fn check_size(width: usize, height: usize) -> Result<(), String> {
+    if width > WIDTH || height > HEIGHT {
+        Err(format!(
+            "the stage is {} x {}, larger than {} x {}",
+            width, height, WIDTH, HEIGHT
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// RETURNS: an error at the header of the last stage if it has no rows
+fn check_rows(stages: &[ImportedStage], header: usize) -> std::io::Result<()> {
+    match stages.last() {
+        Some(stage) if stage.lines.is_empty() => Err(invalid_data(header, "the stage has no rows")),
+        _ => Ok(()),
+    }
+}
+
+pub fn parse_dump(text: &str, tiles: &TileMap) -> std::io::Result<Vec<ImportedStage>> {
+    let mut stages: Vec<ImportedStage> = vec![];
+    let mut width = 0;
+    // THE LINE OF THE HEADER OF THE LAST STAGE
+    let mut header = 0;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+            continue;
+        }
+        if let Some(stage) = line.strip_prefix(STAGE_PREFIX) {
+            let stage = stage
+                .trim()
+                .parse()
+                .map_err(|_| invalid_data(number, "the stage number is not a number"))?;
+            check_rows(&stages, header)?;
+            if stages.iter().any(|s| s.number == stage) {
+                return Err(invalid_data(number, "the stage number is repeated"));
+            }
+            header = number;
+            stages.push(ImportedStage {
+                number: stage,
+                lines: vec![],
+            });
+            continue;
+        }
+        let stage = stages
+            .last_mut()
+            .ok_or_else(|| invalid_data(number, "tiles before the first stage"))?;
+        let ids = line
+            .split_whitespace()
+            .map(parse_id)
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid_data(number, "a tile id is not hexadecimal"))?;
+        if stage.lines.is_empty() {
+            width = ids.len();
+        } else if ids.len() != width {
+            return Err(invalid_data(
+                number,
+                "the rows of a stage have different widths",
+            ));
+        }
+        check_size(width, stage.lines.len() + 1).map_err(|m| invalid_data(number, &m))?;
+        let row = row_to_line(&ids, tiles).map_err(|m| invalid_data(number, &m))?;
+        stage.lines.push(row);
+    }
+    check_rows(&stages, header)?;
+    Ok(stages)
+}
+
+// STAGES NUMBERED FROM 1. BYTES AFTER THE LAST COMPLETE STAGE ARE AN ERROR
+pub fn stages_from_bytes(
+    bytes: &[u8],
+    (width, height): (usize, usize),
+    tiles: &TileMap,
+) -> Result<Vec<ImportedStage>, String> {
+    check_size(width, height)?;
+    let size = width * height;
+    if size == 0 || !bytes.len().is_multiple_of(size) {
+        return Err(format!(
+            "{} bytes are not stages of {} x {} tiles",
+            bytes.len(),
+            width,
+            height
+        ));
+    }
+    bytes
+        .chunks(size)
+        .enumerate()
+        .map(|(index, stage)| {
+            let lines = stage
+                .chunks(width)
+                .map(|row| row_to_line(row, tiles))
+                .collect::<Result<Vec<String>, String>>()
+                .map_err(|m| format!("stage {}: {}", index + 1, m))?;
+            Ok(ImportedStage {
+                number: index + 1,
+                lines,
+            })
+        })
+        .collect()
+}
+
+// RETURNS: a pack with a level named "Stage <number>" for each stage
+pub fn to_pack(stages: &[ImportedStage], name: &str) -> Pack {
+    Pack {
+        name: name.to_string(),
+        levels: stages
+            .iter()
+            .map(|stage| PackLevel {
+                name: format!("Stage {}", stage.number),
+                par: None,
+                lines: stage.lines.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::block::Block;
+    use super::super::solver::{solve, SolverConfig};
+    use super::{parse_dump, stages_from_bytes, to_pack, TileMap};
+
+    // THE BLOCK OF 00 IS A SPACE
+    const TILES: &str = "00  \n01 W\n05 G\n07 @\n10 a\n14 A\n";
+
+    const DUMP: &str = "; TWO SMALL STAGES
+stage 1
+01 01 01 01 01 01 01 01 01
+01 07 00 05 00 00 00 00 01
+01 01 01 01 00 00 00 00 01
+01 05 00 00 00 00 00 00 01
+01 01 01 01 01 01 01 01 01
+
+stage 2
+01 01 01 01 01 01
+01 07 10 14 05 01
+01 01 01 01 01 01
+";
+
+    fn tiles() -> TileMap {
+        TileMap::parse(TILES).unwrap()
+    }
+
+    #[test]
+    fn import_dump() {
+        let stages = parse_dump(DUMP, &tiles()).unwrap();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].number, 1);
+        assert_eq!(stages[0].lines[1], "W@ G    W");
+        assert_eq!(stages[1].lines[1], "W@aAGW");
+        assert_eq!(stages[0].state().ghosts_count(), 2);
+        assert!(solve(&stages[0].state(), &SolverConfig::default()).is_some());
+
+        let pack = to_pack(&stages, "Original");
+        assert_eq!(pack.levels[1].name, "Stage 2");
+        assert!(pack.levels[1].state() == stages[1].state());
+    }
+
+    #[test]
+    fn invalid_dumps() {
+        let tiles = tiles();
+        assert!(parse_dump("01 01\n", &tiles).is_err());
+        assert!(parse_dump("stage 1\n01 01\n01\n", &tiles).is_err());
+        assert!(parse_dump("stage 1\n01 zz\n", &tiles).is_err());
+        assert!(parse_dump("stage 1\n01 ff\n", &tiles).is_err());
+        assert!(parse_dump("stage 1\n01 01 01 01 01 01 01 01 01 01 01 01 01\n", &tiles).is_err());
+        let error = parse_dump("stage 1\n; NOTHING YET\nstage 2\n01 01\n", &tiles).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: the stage has no rows");
+        assert!(parse_dump("stage 1\n01 01\nstage 2\n", &tiles).is_err());
+        let error = parse_dump("stage 1\n01 01\nstage 1\n01 01\n", &tiles).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: the stage number is repeated");
+    }
+
+    #[test]
+    fn custom_tiles_and_bytes() {
+        let tiles = TileMap::parse("; OTHER IDS\n0x80 W\n81 @\n82  \n83 G\n").unwrap();
+        assert_eq!(tiles.block(0x82), Some(Block::Empty));
+        assert_eq!(tiles.block(0x00), None);
+        assert!(TileMap::parse("80 W\n80 G\n").is_err());
+        assert!(TileMap::parse("80 WW\n").is_err());
+
+        #[rustfmt::skip]
+        let bytes = vec![
+            0x80, 0x80, 0x80, 0x80,
+            0x80, 0x81, 0x83, 0x80,
+            0x80, 0x80, 0x80, 0x80,
+            0x80, 0x80, 0x80, 0x80,
+            0x80, 0x83, 0x81, 0x80,
+            0x80, 0x80, 0x80, 0x80,
+        ];
+        let stages = stages_from_bytes(&bytes, (4, 3), &tiles).unwrap();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[1].number, 2);
+        assert_eq!(stages[1].lines, vec!["WWWW", "WG@W", "WWWW"]);
+        assert!(stages_from_bytes(&bytes[1..], (4, 3), &tiles).is_err());
+        assert!(stages_from_bytes(&bytes, (4, 3), &TileMap::parse(TILES).unwrap()).is_err());
+    }
+}
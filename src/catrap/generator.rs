@@ -0,0 +1,330 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+
+use super::block::Block;
+use super::difficulty::{rate_difficulty, Difficulty, Tier};
+use super::random::Random;
+use super::solver::{solve, Solution, SolverConfig};
+use super::stage::{HEIGHT, WIDTH};
+use super::state::State;
+
+type BlockWeights = [(Block, usize); 5];
+
+// BLOCKS OF THE INSIDE OF A LEVEL, WITH THEIR WEIGHT
+const BLOCK_WEIGHTS: BlockWeights = [
+    (Block::Empty, 45),
+    (Block::Wall, 15),
+    (Block::SandWall, 20),
+    (Block::Stair, 8),
+    (Block::Rock, 12),
+];
+
+// A SOLVED LEVEL TO GENERATE BACKWARDS IS MOSTLY EMPTY: UNDOING MOVES FILLS IT
+// WITH SAND, ROCKS AND GHOSTS
+const SOLVED_BLOCK_WEIGHTS: BlockWeights = [
+    (Block::Empty, 70),
+    (Block::Wall, 18),
+    (Block::SandWall, 0),
+    (Block::Stair, 8),
+    (Block::Rock, 4),
+];
+
+// WHEN GENERATING BACKWARDS, NUMBER OF THE DEEPEST STATES THAT ARE SOLVED FOR EACH
+// SOLVED LEVEL. THEY MAY HAVE SHORTER SOLUTIONS THAN THE MOVES UNDONE
+const BACKWARDS_CANDIDATES: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    // SIZE INCLUDING THE SURROUNDING WALLS
+    pub width: usize,
+    pub height: usize,
+    pub ghosts: usize,
+    pub heroes: usize,
+    // LEVELS WITH SHORTER SOLUTIONS ARE REJECTED AS TRIVIAL
+    pub min_solution_length: usize,
+    // LEVELS BELOW THIS TIER ARE REJECTED
+    pub min_tier: Option<Tier>,
+    // STATES EXPLORED TO RATE THE DIFFICULTY, OR TO UNDO MOVES WHEN GENERATING BACKWARDS
+    pub exploration_limit: usize,
+    pub max_attempts: usize,
+    pub seed: u64,
+    pub solver: SolverConfig,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            width: 8,
+            height: 6,
+            ghosts: 2,
+            heroes: 1,
+            min_solution_length: 5,
+            min_tier: None,
+            exploration_limit: 10_000,
+            max_attempts: 1000,
+            seed: 0,
+            solver: SolverConfig::default(),
+        }
+    }
+}
+
+pub struct GeneratedLevel {
+    pub state: State,
+    // IN THE FORMAT OF State::from_lines
+    pub lines: Vec<String>,
+    pub solution: Solution,
+    pub difficulty: Option<Difficulty>,
+    // THE ATTEMPT THAT PRODUCED THE LEVEL, STARTING AT 0
+    pub attempt: usize,
+}
+
+fn random_block(weights: &BlockWeights, random: &mut Random) -> Block {
+    let total: usize = weights.iter().map(|(_, w)| w).sum();
+    let mut n = random.below(total);
+    for (block, weight) in weights.iter() {
+        if n < *weight {
+            return *block;
+        }
+        n -= weight;
+    }
+    unreachable!()
+}
+
+// RETURNS: the lines of a random level, or None if there is no room for the
+// ghosts and heroes. Everything that falls is placed on something, so the level is stable
+fn random_lines(
+    config: &GeneratorConfig,
+    weights: &BlockWeights,
+    ghosts: usize,
+    random: &mut Random,
+) -> Option<Vec<String>> {
+    let (width, height) = (config.width, config.height);
+    let mut grid = vec![vec![Block::Wall; width]; height];
+    for y in (1..height - 1).rev() {
+        let below = &grid[y + 1];
+        let row = (0..width)
+            .map(|x| {
+                if x == 0 || x == width - 1 {
+                    return Block::Wall;
+                }
+                let block = random_block(weights, random);
+                let supported = !Block::is_empty(below[x]);
+                if Block::is_fall(block) && !supported {
+                    Block::Empty
+                } else {
+                    block
+                }
+            })
+            .collect();
+        grid[y] = row;
+    }
+
+    // PLACES A BLOCK IN A RANDOM EMPTY CELL ABOVE A NOT EMPTY ONE
+    fn place(grid: &mut [Vec<Block>], block: Block, random: &mut Random) -> Option<()> {
+        let (width, height) = (grid[0].len(), grid.len());
+        let free: Vec<(usize, usize)> = (1..height - 1)
+            .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+            .filter(|(x, y)| Block::is_empty(grid[*y][*x]) && !Block::is_empty(grid[y + 1][*x]))
+            .collect();
+        if free.is_empty() {
+            return None;
+        }
+        let (x, y) = *random.choose(&free);
+        grid[y][x] = block;
+        Some(())
+    }
+    for _ in 0..ghosts {
+        let ghost = if random.chance(0.5) {
+            Block::Ghost
+        } else {
+            Block::FallingGhost
+        };
+        place(&mut grid, ghost, random)?;
+    }
+    for _ in 0..config.heroes {
+        place(&mut grid, Block::Hero, random)?;
+    }
+
+    Some(
+        grid.iter()
+            .map(|row| row.iter().map(|b| b.to_char()).collect())
+            .collect(),
+    )
+}
+
+// RETURNS: the level if it is solvable and not trivial
+fn accept(state: State, config: &GeneratorConfig, attempt: usize) -> Option<GeneratedLevel> {
+    // A HERO OVER A STAIR CAN NOT BE WRITTEN
+    let lines = state.to_lines()?;
+    let solution = solve(&state, &config.solver)?;
+    if solution.moves.len() < config.min_solution_length {
+        return None;
+    }
+    let difficulty = match config.min_tier {
+        None => None,
+        Some(min_tier) => {
+            let difficulty = rate_difficulty(&state, &config.solver, config.exploration_limit)?;
+            if difficulty.tier < min_tier {
+                return None;
+            }
+            Some(difficulty)
+        }
+    };
+    Some(GeneratedLevel {
+        lines,
+        state,
+        solution,
+        difficulty,
+        attempt,
+    })
+}
+
+fn check_size(config: &GeneratorConfig) {
+    assert!(config.width >= 3 && config.width <= WIDTH);
+    assert!(config.height >= 3 && config.height <= HEIGHT);
+}
+
+// RETURNS: a solvable level that is not trivial, or None if none was found in
+// config.max_attempts attempts. The same seed always gives the same level
+pub fn generate(config: &GeneratorConfig) -> Option<GeneratedLevel> {
+    check_size(config);
+    let mut random = Random::new(config.seed);
+
+    for attempt in 0..config.max_attempts {
+        let lines = match random_lines(config, &BLOCK_WEIGHTS, config.ghosts, &mut random) {
+            Some(lines) => lines,
+            None => continue,
+        };
+        let state = State::from_lines(lines.iter().map(|l| l.as_str()).collect());
+        if let Some(level) = accept(state, config, attempt) {
+            return Some(level);
+        }
+    }
+    None
+}
+
+// RETURNS: the states with config.ghosts ghosts found by undoing moves from solved,
+// in a breadth first search of at most config.exploration_limit states. The deepest
+// ones come first: they need more moves to get back to solved
+#[allow(clippy::mutable_key_type)]
+fn undo_moves(solved: &State, config: &GeneratorConfig, random: &mut Random) -> Vec<State> {
+    let mut depths: HashMap<State, usize> = HashMap::new();
+    depths.insert(solved.clone(), 0);
+    let mut to_see = VecDeque::new();
+    to_see.push_back(solved.clone());
+    let mut found: Vec<(usize, u64, State)> = vec![];
+    let mut keep = |state: &State, depth: usize| {
+        if state.ghosts_count() == config.ghosts && depth >= config.min_solution_length {
+            found.push((depth, random.next_u64(), state.clone()));
+        }
+    };
+
+    'search: while let Some(current) = to_see.pop_front() {
+        let depth = depths[&current];
+        keep(&current, depth);
+        for (_, previous) in current.previous_states() {
+            let too_many_ghosts = previous.ghosts_count() > config.ghosts;
+            if too_many_ghosts || depths.contains_key(&previous) {
+                continue;
+            }
+            if depths.len() >= config.exploration_limit {
+                break 'search;
+            }
+            depths.insert(previous.clone(), depth + 1);
+            to_see.push_back(previous);
+        }
+    }
+    // THE STATES LEFT ARE NOT EXPANDED, BUT THEY ARE THE DEEPEST ONES FOUND
+    for state in to_see.iter() {
+        keep(state, depths[state]);
+    }
+    // RANDOM ORDER AMONG THE STATES OF THE SAME DEPTH
+    found.sort_by_key(|(depth, order, _)| (Reverse(*depth), *order));
+    found.into_iter().map(|(_, _, state)| state).collect()
+}
+
+// LIKE generate, BUT STARTING FROM A RANDOM SOLVED LEVEL AND UNDOING MOVES: THE
+// LEVELS TRIED ARE ALWAYS SOLVABLE AND FAR FROM BEING SOLVED. ONLY THE MOVES THAT
+// State::previous_states UNDOES ARE USED, SO THE SOLVED LEVELS HAVE NO KEYS, DOORS,
+// CRUMBLING FLOORS OR TELEPORTERS
+pub fn generate_backwards(config: &GeneratorConfig) -> Option<GeneratedLevel> {
+    check_size(config);
+    let mut random = Random::new(config.seed);
+
+    for attempt in 0..config.max_attempts {
+        let lines = match random_lines(config, &SOLVED_BLOCK_WEIGHTS, 0, &mut random) {
+            Some(lines) => lines,
+            None => continue,
+        };
+        let solved = State::from_lines(lines.iter().map(|l| l.as_str()).collect());
+        let candidates = undo_moves(&solved, config, &mut random);
+        for state in candidates.into_iter().take(BACKWARDS_CANDIDATES) {
+            if let Some(level) = accept(state, config, attempt) {
+                return Some(level);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::difficulty::Tier;
+    use super::super::state::State;
+    use super::{generate, generate_backwards, GeneratorConfig};
+
+    #[test]
+    fn generated_levels_are_solvable() {
+        let config = GeneratorConfig::default();
+        let level = generate(&config).unwrap();
+        assert!(level.solution.moves.len() >= config.min_solution_length);
+        assert_eq!(level.state.ghosts_count(), config.ghosts);
+
+        let again = State::from_lines(level.lines.iter().map(|l| l.as_str()).collect());
+        assert!(again == level.state);
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        let config = GeneratorConfig {
+            seed: 42,
+            ..Default::default()
+        };
+        let first = generate(&config).unwrap();
+        let second = generate(&config).unwrap();
+        assert_eq!(first.lines, second.lines);
+
+        let other = generate(&GeneratorConfig {
+            seed: 43,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_ne!(first.lines, other.lines);
+    }
+
+    #[test]
+    fn minimum_tier() {
+        let config = GeneratorConfig {
+            min_tier: Some(Tier::Easy),
+            seed: 7,
+            ..Default::default()
+        };
+        let level = generate(&config).unwrap();
+        assert!(level.difficulty.unwrap().tier >= Tier::Easy);
+    }
+
+    #[test]
+    fn backwards() {
+        let config = GeneratorConfig {
+            min_solution_length: 8,
+            seed: 3,
+            ..Default::default()
+        };
+        let level = generate_backwards(&config).unwrap();
+        assert_eq!(level.state.ghosts_count(), config.ghosts);
+        assert!(level.solution.moves.len() >= config.min_solution_length);
+
+        let again = generate_backwards(&config).unwrap();
+        assert_eq!(level.lines, again.lines);
+    }
+}
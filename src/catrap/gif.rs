@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use super::block::Block;
+use super::moves::Move;
+use super::state::State;
+
+// SIZE OF A CELL, IN PIXELS
+const CELL: usize = 16;
+// HOW LONG A FRAME IS SHOWN, IN HUNDREDTHS OF A SECOND
+const MOVE_DELAY: u16 = 30;
+const FALL_DELAY: u16 = 8;
+const END_DELAY: u16 = 150;
+
+// INDICES IN THE PALETTE
+const BACKGROUND: u8 = 0;
+const WALL: u8 = 1;
+const WALL_SHADOW: u8 = 2;
+const SAND: u8 = 3;
+const SAND_DOTS: u8 = 4;
+const ROCK: u8 = 5;
+const GHOST: u8 = 6;
+const FALLING_GHOST: u8 = 7;
+const STAIR: u8 = 8;
+const HERO: u8 = 9;
+const ACTIVE_HERO: u8 = 10;
+const TELEPORTER: u8 = 11;
+// ONE COLOUR PER KIND OF KEY AND DOOR
+const KINDS: u8 = 12;
+
+const PALETTE: [[u8; 3]; 16] = [
+    [0x20, 0x20, 0x20],
+    [0x70, 0x70, 0x70],
+    [0x50, 0x50, 0x50],
+    [0xd8, 0xb8, 0x68],
+    [0xa0, 0x80, 0x40],
+    [0x8b, 0x5a, 0x2b],
+    [0xe8, 0xe8, 0xff],
+    [0xa0, 0xa0, 0xe0],
+    [0xc0, 0x80, 0x40],
+    [0x40, 0xc0, 0x40],
+    [0xff, 0xff, 0xff],
+    [0xc0, 0x40, 0xc0],
+    [0xe0, 0x40, 0x40],
+    [0x40, 0x80, 0xe0],
+    [0x40, 0xe0, 0xa0],
+    [0xe0, 0xc0, 0x40],
+];
+
+// 16 COLOURS NEED 4 BITS
+const MIN_CODE_SIZE: u8 = 4;
+const MAX_CODE: u16 = 4095;
+
+// AN IMAGE WITH THE COLOURS OF PALETTE
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![BACKGROUND; width * height],
+        }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, colour: u8) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.pixels[py * self.width + px] = colour;
+            }
+        }
+    }
+
+    // FILLS THE CIRCLE INSIDE THE SQUARE OF THE GIVEN SIZE
+    fn fill_circle(&mut self, x: usize, y: usize, size: usize, colour: u8) {
+        let (center_x, center_y) = (2 * x + size, 2 * y + size);
+        for py in y..y + size {
+            for px in x..x + size {
+                let dx = (2 * px + 1) as isize - center_x as isize;
+                let dy = (2 * py + 1) as isize - center_y as isize;
+                if dx * dx + dy * dy <= (size * size) as isize {
+                    self.fill_rect(px, py, 1, 1, colour);
+                }
+            }
+        }
+    }
+
+    fn draw_block(&mut self, block: Block, x: usize, y: usize) {
+        match block {
+            Block::Empty | Block::Hero => {}
+            Block::Wall => {
+                self.fill_rect(x, y, CELL, CELL, WALL);
+                self.fill_rect(x, y + CELL - 1, CELL, 1, WALL_SHADOW);
+                self.fill_rect(x + CELL - 1, y, 1, CELL, WALL_SHADOW);
+            }
+            Block::SandWall => {
+                self.fill_rect(x, y, CELL, CELL, SAND);
+                for dy in (2..CELL).step_by(4) {
+                    for dx in (2 + dy % 8 / 2..CELL).step_by(4) {
+                        self.fill_rect(x + dx, y + dy, 1, 1, SAND_DOTS);
+                    }
+                }
+            }
+            Block::Rock => self.fill_circle(x + 1, y + 1, CELL - 2, ROCK),
+            Block::Ghost | Block::FallingGhost => {
+                let colour = if block == Block::Ghost {
+                    GHOST
+                } else {
+                    FALLING_GHOST
+                };
+                self.fill_circle(x + 2, y + 2, CELL - 4, colour);
+                self.fill_rect(x + 2, y + CELL / 2, CELL - 4, CELL / 2 - 2, colour);
+                self.fill_rect(x + 5, y + 6, 2, 2, BACKGROUND);
+                self.fill_rect(x + CELL - 7, y + 6, 2, 2, BACKGROUND);
+            }
+            Block::Stair => {
+                self.fill_rect(x + 3, y, 2, CELL, STAIR);
+                self.fill_rect(x + CELL - 5, y, 2, CELL, STAIR);
+                for dy in (2..CELL).step_by(4) {
+                    self.fill_rect(x + 3, y + dy, CELL - 6, 1, STAIR);
+                }
+            }
+            Block::Key(kind) => {
+                let colour = KINDS + kind % 4;
+                self.fill_circle(x + 1, y + 5, 6, colour);
+                self.fill_rect(x + 6, y + 7, CELL - 7, 2, colour);
+                self.fill_rect(x + CELL - 4, y + 9, 2, 3, colour);
+            }
+            Block::Door(kind) => {
+                self.fill_rect(x + 2, y, CELL - 4, CELL, KINDS + kind % 4);
+                self.fill_rect(x + CELL - 6, y + CELL / 2, 2, 2, BACKGROUND);
+            }
+            Block::CrumblingFloor => {
+                self.fill_rect(x, y + CELL / 2, CELL, CELL / 2, WALL);
+                self.fill_rect(x + 5, y + CELL / 2, 1, CELL / 2, BACKGROUND);
+                self.fill_rect(x + 11, y + CELL / 2, 1, CELL / 2, BACKGROUND);
+            }
+            Block::Teleporter(_) => {
+                self.fill_circle(x + 1, y + 1, CELL - 2, TELEPORTER);
+                self.fill_circle(x + 4, y + 4, CELL - 8, BACKGROUND);
+            }
+        }
+    }
+}
+
+// RETURNS: the image of the first columns and lines of state
+pub fn render(state: &State, columns: usize, lines: usize) -> Image {
+    let mut image = Image::new(columns * CELL, lines * CELL);
+    for line in 0..lines {
+        for column in 0..columns {
+            let block = state.block_at(&(column as i32, line as i32));
+            image.draw_block(block, column * CELL, line * CELL);
+        }
+    }
+    for (index, (x, y)) in state.heroes().iter().enumerate() {
+        let (x, y) = (*x as usize * CELL, *y as usize * CELL);
+        if state.active_hero() == Some(index) {
+            image.fill_circle(x, y, CELL, ACTIVE_HERO);
+        }
+        image.fill_circle(x + 2, y + 2, CELL - 4, HERO);
+    }
+    image
+}
+
+// RETURNS: the states to show when playing back a solution, with the delay of each one.
+// The blocks that fall after a move are shown falling one place at a time
+pub fn playback_frames(states: &[State]) -> Vec<(State, u16)> {
+    let mut frames: Vec<(State, u16)> = states
+        .iter()
+        .take(1)
+        .map(|s| (s.clone(), MOVE_DELAY))
+        .collect();
+    for pair in states.windows(2) {
+        let steps = match pair[0].move_to(&pair[1]) {
+            Some(Move::Step(hero, direction)) => {
+                pair[0].next_state_traced(hero, direction).unwrap().frames()
+            }
+            _ => vec![pair[1].clone()],
+        };
+        let count = steps.len();
+        for (index, step) in steps.into_iter().enumerate() {
+            let delay = if index + 1 < count {
+                FALL_DELAY
+            } else {
+                MOVE_DELAY
+            };
+            frames.push((step, delay));
+        }
+    }
+    if let Some(last) = frames.last_mut() {
+        last.1 = END_DELAY;
+    }
+    frames
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// RETURNS: the LZW codes of the pixels, as GIF stores them
+fn lzw_compress(pixels: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut writer = BitWriter::default();
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+    writer.write(clear, size);
+
+    let mut prefix: Option<u16> = None;
+    for &pixel in pixels {
+        let current = match prefix {
+            None => {
+                prefix = Some(pixel as u16);
+                continue;
+            }
+            Some(current) => current,
+        };
+        if let Some(code) = codes.get(&(current, pixel)) {
+            prefix = Some(*code);
+            continue;
+        }
+        writer.write(current, size);
+        codes.insert((current, pixel), next);
+        if next >= 1 << size {
+            size += 1;
+        }
+        if next == MAX_CODE {
+            writer.write(clear, size);
+            codes.clear();
+            size = MIN_CODE_SIZE + 1;
+            next = end;
+        }
+        next += 1;
+        prefix = Some(pixel as u16);
+    }
+    if let Some(current) = prefix {
+        writer.write(current, size);
+        // THE DECODER ADDS A CODE AFTER READING THE LAST ONE, AND MAY NEED ONE MORE BIT
+        if next == 1 << size && size < 12 {
+            size += 1;
+        }
+    }
+    writer.write(end, size);
+    writer.finish()
+}
+
+fn write_u16<W: Write>(value: u16, output: &mut W) -> std::io::Result<()> {
+    output.write_all(&value.to_le_bytes())
+}
+
+// WRITES AN ANIMATED GIF THAT PLAYS BACK THE STATES OF A SOLUTION IN A LOOP
+pub fn write_gif<W: Write>(states: &[State], output: &mut W) -> std::io::Result<()> {
+    let frames = playback_frames(states);
+    if frames.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "no states to play back",
+        ));
+    }
+    let (columns, lines) = states
+        .iter()
+        .map(State::drawn_size)
+        .fold((1, 1), |(w, h), (sw, sh)| (w.max(sw), h.max(sh)));
+    let (width, height) = ((columns * CELL) as u16, (lines * CELL) as u16);
+
+    output.write_all(b"GIF89a")?;
+    write_u16(width, output)?;
+    write_u16(height, output)?;
+    // GLOBAL PALETTE OF 2^(3+1) COLOURS, 8 BITS PER PRIMARY COLOUR
+    output.write_all(&[0xf3, BACKGROUND, 0])?;
+    for colour in PALETTE.iter() {
+        output.write_all(colour)?;
+    }
+    // LOOPS FOREVER
+    output.write_all(&[0x21, 0xff, 0x0b])?;
+    output.write_all(b"NETSCAPE2.0")?;
+    output.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+    for (state, delay) in frames.iter() {
+        output.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        write_u16(*delay, output)?;
+        output.write_all(&[0x00, 0x00])?;
+
+        output.write_all(&[0x2c])?;
+        write_u16(0, output)?;
+        write_u16(0, output)?;
+        write_u16(width, output)?;
+        write_u16(height, output)?;
+        output.write_all(&[0x00, MIN_CODE_SIZE])?;
+        let image = render(state, columns, lines);
+        for block in lzw_compress(&image.pixels).chunks(255) {
+            output.write_all(&[block.len() as u8])?;
+            output.write_all(block)?;
+        }
+        output.write_all(&[0x00])?;
+    }
+    output.write_all(&[0x3b])
+}
+
+pub fn solution_to_gif(states: &[State]) -> Vec<u8> {
+    let mut output = vec![];
+    write_gif(states, &mut output).unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::block::Block;
+    use super::super::direction::Direction;
+    use super::super::solve_catrap;
+    use super::super::state::State;
+    use super::{
+        lzw_compress, playback_frames, render, solution_to_gif, FALL_DELAY, MIN_CODE_SIZE,
+    };
+
+    // THE USUAL GIF DECODER, TO CHECK THE ENCODER
+    fn lzw_decompress(data: &[u8]) -> Vec<u8> {
+        let clear = 1usize << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> { (0..=end).map(|c| vec![c as u8]).collect() };
+        let mut table = reset();
+        let mut size = MIN_CODE_SIZE as usize + 1;
+        let (mut buffer, mut bits, mut position) = (0u32, 0, 0);
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = vec![];
+        loop {
+            while bits < size {
+                buffer |= (data[position] as u32) << bits;
+                position += 1;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << size) - 1)) as usize;
+            buffer >>= size;
+            bits -= size;
+            if code == clear {
+                table = reset();
+                size = MIN_CODE_SIZE as usize + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (&previous, code < table.len()) {
+                (_, true) => table[code].clone(),
+                (Some(previous), false) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, false) => panic!("code {} out of the table", code),
+            };
+            output.extend_from_slice(&entry);
+            if let Some(previous) = previous {
+                table.push([previous, vec![entry[0]]].concat());
+            }
+            previous = Some(entry);
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let uniform = vec![3; 10_000];
+        assert_eq!(lzw_decompress(&lzw_compress(&uniform)), uniform);
+
+        // ENOUGH DIFFERENT SEQUENCES TO FILL THE TABLE AND CLEAR IT
+        let mut seed = 7u32;
+        let noise: Vec<u8> = (0..50_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8 % 16
+            })
+            .collect();
+        assert_eq!(lzw_decompress(&lzw_compress(&noise)), noise);
+
+        for length in 1..300 {
+            let pixels: Vec<u8> = (0..length).map(|i| (i * i % 7) as u8).collect();
+            assert_eq!(lzw_decompress(&lzw_compress(&pixels)), pixels);
+        }
+    }
+
+    #[test]
+    fn falls_are_shown() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWW",
+            "W@R   W",
+            "WW    W",
+            "WW    W",
+            "WWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let next = state.next_state(0, Direction::Right).unwrap();
+        let frames = playback_frames(&[state.clone(), next.clone()]);
+        // THE FIRST STATE, THE PUSH AND TWO PLACES OF FALL
+        assert_eq!(frames.len(), 4);
+        assert!(frames[0].0 == state);
+        assert_eq!(frames[1].1, FALL_DELAY);
+        assert!(frames[3].0 == next);
+    }
+
+    #[test]
+    fn high_fall() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W  R    W",
+            "W  R    W",
+            "W @R    W",
+            "WWWW    W",
+            "W       W",
+            "W       W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let next = state.next_state(0, Direction::Right).unwrap();
+
+        // THE PUSH, TWO ROCKS FALLING ONE PLACE AND ONE FALLING THREE PLACES
+        let trace = state.next_state_traced(0, Direction::Right).unwrap();
+        assert_eq!(trace.falls().len(), 3);
+        let steps = trace.frames();
+        assert_eq!(steps.len(), 1 + 2 + 3);
+        assert!(steps.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(*steps.last().unwrap() == next);
+        assert!(matches!(steps[0].block_at(&(4, 3)), Block::Rock));
+        assert!(matches!(steps[0].block_at(&(3, 1)), Block::Rock));
+
+        let frames = playback_frames(&[state.clone(), next.clone()]);
+        assert_eq!(frames.len(), 1 + steps.len());
+        assert!(frames[1..frames.len() - 1]
+            .iter()
+            .all(|(_, delay)| *delay == FALL_DELAY));
+        assert!(frames.last().unwrap().0 == next);
+    }
+
+    #[test]
+    fn animated_gif() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W@ G R  W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let states = solve_catrap(&state).unwrap();
+        let gif = solution_to_gif(&states);
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(*gif.last().unwrap(), 0x3b);
+        let (width, height) = (
+            u16::from_le_bytes([gif[6], gif[7]]),
+            u16::from_le_bytes([gif[8], gif[9]]),
+        );
+        assert_eq!((width, height), (9 * 16, 5 * 16));
+
+        // THE FIRST FRAME COMES AFTER THE HEADER, THE PALETTE, THE LOOP AND THE DELAY
+        let mut position = 13 + 16 * 3 + 19 + 8;
+        assert_eq!(gif[position], 0x2c);
+        position += 10;
+        assert_eq!(gif[position], MIN_CODE_SIZE);
+        position += 1;
+        let mut data = vec![];
+        while gif[position] != 0 {
+            let length = gif[position] as usize;
+            data.extend_from_slice(&gif[position + 1..position + 1 + length]);
+            position += 1 + length;
+        }
+        let image = render(&state, 9, 5);
+        assert_eq!(lzw_decompress(&data), image.pixels);
+
+        let frames = gif.windows(2).filter(|w| w == &[0x21, 0xf9]).count();
+        assert!(frames > states.len());
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use super::direction::Direction;
+use super::moves::Move;
+use super::solver::{solve, SolverConfig};
+use super::state::State;
+
+// REMEMBERS THE SOLUTIONS FOUND: A SUFFIX OF AN OPTIMAL SOLUTION IS OPTIMAL TOO, SO
+// A PLAYER FOLLOWING THE HINTS NEVER WAITS FOR THE SOLVER AGAIN
+pub struct Hinter {
+    config: SolverConfig,
+    // THE HINT OF EVERY STATE SEEN, None IF THE STATE IS SOLVED OR CAN NOT BE SOLVED
+    hints: HashMap<State, Option<(usize, Direction)>>,
+    // NUMBER OF TIMES THE SOLVER WAS CALLED
+    solves: usize,
+}
+
+impl Hinter {
+    pub fn new(config: SolverConfig) -> Hinter {
+        Hinter {
+            config,
+            hints: HashMap::new(),
+            solves: 0,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn solves(&self) -> usize {
+        self.solves
+    }
+
+    // RETURNS: the hero and direction of the first step of an optimal solution from
+    // state. The switches between heroes are left out, the hero index tells which one to play
+    pub fn hint(&mut self, state: &State) -> Option<(usize, Direction)> {
+        if let Some(hint) = self.hints.get(state) {
+            return *hint;
+        }
+        if state.ghosts_count() == 0 {
+            self.hints.insert(state.clone(), None);
+            return None;
+        }
+        self.solves += 1;
+        let solution = match solve(state, &self.config) {
+            Some(solution) => solution,
+            None => {
+                self.hints.insert(state.clone(), None);
+                return None;
+            }
+        };
+
+        let mut next_step = None;
+        for (index, a_move) in solution.moves.iter().enumerate().rev() {
+            if let Move::Step(hero, direction) = a_move {
+                next_step = Some((*hero, *direction));
+            }
+            self.hints.insert(solution.states[index].clone(), next_step);
+        }
+        // THE SOLVER MAY START FROM A COPY WITH AN ACTIVE HERO
+        self.hints.insert(state.clone(), next_step);
+        next_step
+    }
+}
+
+impl Default for Hinter {
+    fn default() -> Self {
+        Hinter::new(SolverConfig::default())
+    }
+}
+
+// RETURNS: the first step of an optimal solution, without any cache
+#[cfg(test)]
+pub fn hint(state: &State) -> Option<(usize, Direction)> {
+    Hinter::default().hint(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::direction::Direction;
+    use super::super::solver::SolverConfig;
+    use super::super::state::State;
+    use super::{hint, Hinter};
+
+    #[test]
+    fn following_hints_solves_the_level() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let mut state = State::from_lines(strings);
+        assert_eq!(hint(&state), Some((0, Direction::Right)));
+
+        let mut hinter = Hinter::default();
+        while let Some((hero, direction)) = hinter.hint(&state) {
+            state = state.next_state(hero, direction).unwrap();
+        }
+        assert_eq!(state.ghosts_count(), 0);
+        assert_eq!(hinter.solves(), 1);
+    }
+
+    #[test]
+    fn hint_after_a_bad_move() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWW",
+            "W  @  GW",
+            "WWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let mut hinter = Hinter::default();
+        assert_eq!(hinter.hint(&state), Some((0, Direction::Right)));
+
+        let state = state.next_state(0, Direction::Left).unwrap();
+        assert_eq!(hinter.hint(&state), Some((0, Direction::Right)));
+        assert_eq!(hinter.solves(), 2);
+    }
+
+    #[test]
+    fn no_hint() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWW",
+            "W@ W GW",
+            "WWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let mut hinter = Hinter::new(SolverConfig::default());
+        assert_eq!(hinter.hint(&state), None);
+        assert_eq!(hinter.hint(&state), None);
+        assert_eq!(hinter.solves(), 1);
+    }
+
+    #[test]
+    fn switches_are_left_out() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W@G  G@ W",
+            "WWWWWWWWW",
+        ];
+        let mut state = State::from_lines(strings);
+        let config = SolverConfig {
+            one_hero_at_a_time: true,
+            ..Default::default()
+        };
+        let mut hinter = Hinter::new(config);
+        let mut heroes_played = vec![];
+        while let Some((hero, direction)) = hinter.hint(&state) {
+            heroes_played.push(hero);
+            state = state
+                .with_active_hero(hero)
+                .next_state(hero, direction)
+                .unwrap();
+        }
+        assert_eq!(state.ghosts_count(), 0);
+        heroes_played.sort();
+        assert_eq!(heroes_played, vec![0, 1]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use pathfinding::num_traits::Zero;
+
+use super::cost::Cost;
+use super::state::State;
+
+enum Search {
+    Found(Cost),
+    // THE SMALLEST COST OVER THE BOUND, NONE IF THERE IS NOTHING LEFT TO EXPLORE
+    Exceeded(Option<Cost>),
+}
+
+struct IdaStar<'a, FN, FS> {
+    successors: &'a FN,
+    success: &'a FS,
+    // BEST COST SEEN FOR A STATE IN THE CURRENT ITERATION
+    table: HashMap<State, Cost>,
+    table_size: usize,
+}
+
+impl<'a, FN, FS> IdaStar<'a, FN, FS>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+    FS: Fn(&State) -> bool,
+{
+    fn search(&mut self, path: &mut Vec<State>, cost: Cost, bound: Cost) -> Search {
+        if cost > bound {
+            return Search::Exceeded(Some(cost));
+        }
+        let current = path.last().unwrap().clone();
+        if (self.success)(&current) {
+            return Search::Found(cost);
+        }
+        match self.table.get(&current) {
+            Some(seen) if *seen <= cost => return Search::Exceeded(None),
+            Some(_) => {
+                self.table.insert(current.clone(), cost);
+            }
+            None if self.table.len() < self.table_size => {
+                self.table.insert(current.clone(), cost);
+            }
+            None => {}
+        }
+
+        let mut next_bound: Option<Cost> = None;
+        for (next, step_cost) in (self.successors)(&current) {
+            if path.contains(&next) {
+                continue;
+            }
+            path.push(next);
+            match self.search(path, cost + step_cost, bound) {
+                Search::Found(found) => return Search::Found(found),
+                Search::Exceeded(Some(exceeded)) => {
+                    next_bound = Some(next_bound.map_or(exceeded, |b| b.min(exceeded)));
+                }
+                Search::Exceeded(None) => {}
+            }
+            path.pop();
+        }
+        Search::Exceeded(next_bound)
+    }
+}
+
+// ITERATIVE DEEPENING A* WITH A ZERO HEURISTIC. MEMORY IS BOUNDED BY THE PATH LENGTH
+// AND BY table_size, THE MAXIMUM NUMBER OF STATES REMEMBERED IN EACH ITERATION
+pub fn ida_star<FN, FS>(
+    start: &State,
+    successors: FN,
+    success: FS,
+    table_size: usize,
+) -> Option<(Vec<State>, Cost)>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+    FS: Fn(&State) -> bool,
+{
+    let mut ida = IdaStar {
+        successors: &successors,
+        success: &success,
+        table: HashMap::new(),
+        table_size,
+    };
+    let mut bound = Cost::zero();
+    loop {
+        let mut path = vec![start.clone()];
+        ida.table.clear();
+        match ida.search(&mut path, Cost::zero(), bound) {
+            Search::Found(cost) => return Some((path, cost)),
+            Search::Exceeded(Some(next_bound)) => bound = next_bound,
+            Search::Exceeded(None) => return None,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use pathfinding::num_traits::Zero;
+use pathfinding::prelude::{build_path, dijkstra_all};
+
+use super::cost::Cost;
+use super::state::State;
+
+// RETURNS: the states reachable from state by moves that do not change the stage
+// (walking, climbing, heroes falling, teleporting, switching heroes), as parents
+// of a dijkstra search
+#[allow(clippy::mutable_key_type)]
+fn walking_region<FN>(state: &State, successors: &FN) -> HashMap<State, (State, Cost)>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    dijkstra_all(state, |st: &State| {
+        successors(st)
+            .into_iter()
+            .filter(|(next, _)| next.same_stage(state))
+            .collect::<Vec<_>>()
+    })
+}
+
+// SUCCESSORS THAT ONLY BRANCH ON STAGE CHANGING MOVES (DIG, PUSH, KILL, FALL...).
+// EACH ONE IS REACHED BY WALKING AND THEN DOING A STAGE CHANGING MOVE
+#[allow(clippy::mutable_key_type)]
+pub fn macro_successors<FN>(state: &State, successors: &FN) -> Vec<(State, Cost)>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    let region = walking_region(state, successors);
+    let walked = std::iter::once((state, Cost::zero()))
+        .chain(region.iter().map(|(st, (_, cost))| (st, *cost)));
+
+    let mut ret: HashMap<State, Cost> = HashMap::new();
+    for (from, walk_cost) in walked {
+        for (next, cost) in successors(from) {
+            if next.same_stage(state) {
+                continue;
+            }
+            let cost = walk_cost + cost;
+            let best = ret.entry(next).or_insert(cost);
+            if cost < *best {
+                *best = cost;
+            }
+        }
+    }
+    ret.into_iter().collect()
+}
+
+// RETURNS: the single moves path between two consecutive states of a macro moves path
+#[allow(clippy::mutable_key_type)]
+fn expand_macro_move<FN>(from: &State, to: &State, successors: &FN) -> Vec<State>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    let region = walking_region(from, successors);
+    let walked = std::iter::once((from, Cost::zero()))
+        .chain(region.iter().map(|(st, (_, cost))| (st, *cost)));
+
+    let (last_walked, _) = walked
+        .filter_map(|(st, walk_cost)| {
+            successors(st)
+                .into_iter()
+                .find(|(next, _)| next == to)
+                .map(|(_, cost)| (st, walk_cost + cost))
+        })
+        .min_by_key(|(_, cost)| *cost)
+        .unwrap();
+
+    let mut ret = build_path(last_walked, &region);
+    ret.push(to.clone());
+    ret
+}
+
+pub fn expand_macro_moves<FN>(states: Vec<State>, successors: &FN) -> Vec<State>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    let mut ret = vec![states[0].clone()];
+    for pair in states.windows(2) {
+        ret.extend(expand_macro_move(&pair[0], &pair[1], successors).into_iter().skip(1));
+    }
+    ret
+}
@@ -0,0 +1,198 @@
+use super::block::Block;
+use super::state::State;
+
+type Grid = Vec<Vec<char>>;
+
+pub struct Minimized {
+    pub state: State,
+    // IN THE FORMAT OF State::from_lines
+    pub lines: Vec<String>,
+    // NUMBER OF TIMES THE PREDICATE WAS EVALUATED
+    pub tests: usize,
+}
+
+struct Minimizer<P: FnMut(&State) -> bool> {
+    predicate: P,
+    tests: usize,
+}
+
+impl<P: FnMut(&State) -> bool> Minimizer<P> {
+    fn holds(&mut self, grid: &Grid) -> bool {
+        self.tests += 1;
+        (self.predicate)(&to_state(grid))
+    }
+
+    // REMOVES ROWS AND COLUMNS ONE AT A TIME. RETURNS: true if something was removed
+    fn crop(&mut self, grid: &mut Grid) -> bool {
+        let mut changed = false;
+        let mut y = 0;
+        while y < grid.len() && grid.len() > 1 {
+            let mut candidate = grid.clone();
+            candidate.remove(y);
+            if self.holds(&candidate) {
+                *grid = candidate;
+                changed = true;
+            } else {
+                y += 1;
+            }
+        }
+        let mut x = 0;
+        while x < grid[0].len() && grid[0].len() > 1 {
+            let mut candidate = grid.clone();
+            candidate.iter_mut().for_each(|row| {
+                row.remove(x);
+            });
+            if self.holds(&candidate) {
+                *grid = candidate;
+                changed = true;
+            } else {
+                x += 1;
+            }
+        }
+        changed
+    }
+
+    // DELTA DEBUGGING: REPLACES CHUNKS OF THE CELLS MATCHING replaceable WITH
+    // replacement, HALVING THE CHUNKS WHEN NO CHUNK CAN BE REPLACED.
+    // RETURNS: true if some cell was replaced
+    fn replace<F: Fn(char) -> bool>(
+        &mut self,
+        grid: &mut Grid,
+        replaceable: F,
+        replacement: Block,
+    ) -> bool {
+        let replacement = replacement.to_char();
+        let mut cells: Vec<(usize, usize)> = (0..grid.len())
+            .flat_map(|y| (0..grid[y].len()).map(move |x| (x, y)))
+            .filter(|(x, y)| replaceable(grid[*y][*x]))
+            .collect();
+        let mut changed = false;
+        let mut chunks = 2.min(cells.len());
+        while !cells.is_empty() {
+            let size = cells.len().div_ceil(chunks);
+            let mut replaced = None;
+            for (index, chunk) in cells.chunks(size).enumerate() {
+                let mut candidate = grid.clone();
+                chunk
+                    .iter()
+                    .for_each(|(x, y)| candidate[*y][*x] = replacement);
+                if self.holds(&candidate) {
+                    *grid = candidate;
+                    replaced = Some(index);
+                    break;
+                }
+            }
+            match replaced {
+                Some(index) => {
+                    let end = (index * size + size).min(cells.len());
+                    cells.drain(index * size..end);
+                    chunks = (chunks - 1).max(2).min(cells.len());
+                    changed = true;
+                }
+                None if size == 1 => break,
+                None => chunks = (chunks * 2).min(cells.len()),
+            }
+        }
+        changed
+    }
+}
+
+fn to_grid(state: &State) -> Option<Grid> {
+    let lines = state.to_lines()?;
+    Some(lines.iter().map(|l| l.chars().collect()).collect())
+}
+
+fn to_lines(grid: &Grid) -> Vec<String> {
+    grid.iter().map(|row| row.iter().collect()).collect()
+}
+
+fn to_state(grid: &Grid) -> State {
+    let lines = to_lines(grid);
+    State::from_lines(lines.iter().map(|l| l.as_str()).collect())
+}
+
+// RETURNS: a smaller level for which predicate still holds, made by cropping rows
+// and columns and by replacing blocks with walls, then with empty cells, until
+// nothing more can be removed. None if predicate does not hold for state, or if
+// state can not be written as lines
+pub fn minimize<P: FnMut(&State) -> bool>(state: &State, predicate: P) -> Option<Minimized> {
+    let mut minimizer = Minimizer {
+        predicate,
+        tests: 0,
+    };
+    let mut grid = to_grid(state)?;
+    if !minimizer.holds(&grid) {
+        return None;
+    }
+    let wall = Block::Wall.to_char();
+    let empty = Block::Empty.to_char();
+    loop {
+        let cropped = minimizer.crop(&mut grid);
+        let walled = minimizer.replace(&mut grid, |c| c != wall, Block::Wall);
+        let emptied = minimizer.replace(&mut grid, |c| c != wall && c != empty, Block::Empty);
+        if !cropped && !walled && !emptied {
+            break;
+        }
+    }
+    Some(Minimized {
+        state: to_state(&grid),
+        lines: to_lines(&grid),
+        tests: minimizer.tests,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solver::{solve, SolverConfig};
+    use super::super::state::State;
+    use super::minimize;
+
+    #[test]
+    fn unsolvable_level() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWWW",
+            "W   R  G W",
+            "W@ ***WW W",
+            "W =    WGW",
+            "W =  G WWW",
+            "WWWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let unsolvable = |s: &State| solve(s, &SolverConfig::default()).is_none();
+        assert!(unsolvable(&state));
+
+        let minimized = minimize(&state, unsolvable).unwrap();
+        minimized.state.dump_stdout();
+        assert!(unsolvable(&minimized.state));
+        // A GHOST THAT CAN NOT BE REACHED IS ENOUGH
+        assert_eq!(minimized.lines, vec!["G"]);
+        assert!(minimized.tests > 0);
+    }
+
+    #[test]
+    fn predicate_must_hold() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWW",
+            "W@ GW",
+            "WWWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert!(minimize(&state, |s| s.ghosts_count() == 0).is_none());
+
+        let minimized =
+            minimize(&state, |s| s.heroes_count() == 1 && s.ghosts_count() == 1).unwrap();
+        assert_eq!(minimized.state.ghosts_count(), 1);
+        assert_eq!(minimized.state.heroes_count(), 1);
+        assert_eq!(
+            minimized
+                .lines
+                .concat()
+                .chars()
+                .filter(|c| *c != 'W')
+                .count(),
+            2
+        );
+    }
+}
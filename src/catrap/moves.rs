@@ -0,0 +1,15 @@
+use super::direction::Direction;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Move {
+    // A HERO TRIES TO MOVE IN A DIRECTION
+    Step(usize, Direction),
+    // THE CONTROL GOES TO ANOTHER HERO (ONLY WHEN HEROES ARE CONTROLLED ONE AT A TIME)
+    Switch(usize),
+}
+
+impl Move {
+    pub fn is_switch(&self) -> bool {
+        matches!(self, Move::Switch(_))
+    }
+}
@@ -0,0 +1,212 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use pathfinding::num_traits::Zero;
+
+use super::cost::Cost;
+use super::solver::{initial_state, single_moves, Solution, SolverConfig};
+use super::state::State;
+
+pub struct OptimalSolutions {
+    pub cost: Cost,
+    // NUMBER OF DISTINCT OPTIMAL PATHS, EVEN THOSE NOT ENUMERATED
+    pub count: u128,
+    // AT MOST limit OF THEM
+    pub solutions: Vec<Solution>,
+}
+
+impl OptimalSolutions {
+    pub fn is_unique(&self) -> bool {
+        self.count == 1
+    }
+}
+
+struct Node {
+    state: State,
+    cost: Cost,
+    // EVERY PREVIOUS NODE IN AN OPTIMAL PATH TO THIS ONE
+    parents: Vec<usize>,
+}
+
+// DIJKSTRA THAT KEEPS ALL THE OPTIMAL PARENTS OF EVERY STATE. IT STOPS AFTER THE
+// COST OF THE FIRST SOLVED STATE, SO ALL THE OPTIMAL SOLUTIONS ARE FOUND
+#[allow(clippy::mutable_key_type)]
+fn optimal_graph(start: &State, config: &SolverConfig) -> (Vec<Node>, Vec<usize>) {
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        cost: Cost::zero(),
+        parents: vec![],
+    }];
+    let mut indices: HashMap<State, usize> = HashMap::new();
+    indices.insert(start.clone(), 0);
+    let mut to_see = BinaryHeap::new();
+    to_see.push(Reverse((Cost::zero(), 0)));
+    let mut goals: Vec<usize> = vec![];
+
+    while let Some(Reverse((cost, index))) = to_see.pop() {
+        if cost > nodes[index].cost {
+            continue;
+        }
+        if let Some(goal) = goals.first() {
+            if cost > nodes[*goal].cost {
+                break;
+            }
+        }
+        if nodes[index].state.ghosts_count() == 0 {
+            goals.push(index);
+            continue;
+        }
+        for (next, step_cost) in single_moves(&nodes[index].state, config) {
+            let next_cost = cost + step_cost;
+            match indices.get(&next) {
+                Some(&n) if next_cost > nodes[n].cost => {}
+                Some(&n) if next_cost == nodes[n].cost => nodes[n].parents.push(index),
+                Some(&n) => {
+                    nodes[n].cost = next_cost;
+                    nodes[n].parents = vec![index];
+                    to_see.push(Reverse((next_cost, n)));
+                }
+                None => {
+                    indices.insert(next.clone(), nodes.len());
+                    to_see.push(Reverse((next_cost, nodes.len())));
+                    nodes.push(Node {
+                        state: next,
+                        cost: next_cost,
+                        parents: vec![index],
+                    });
+                }
+            }
+        }
+    }
+    (nodes, goals)
+}
+
+fn count_paths(nodes: &[Node], index: usize, counts: &mut HashMap<usize, u128>) -> u128 {
+    if nodes[index].parents.is_empty() {
+        return 1;
+    }
+    if let Some(count) = counts.get(&index) {
+        return *count;
+    }
+    let count = nodes[index]
+        .parents
+        .iter()
+        .map(|p| count_paths(nodes, *p, counts))
+        .fold(0u128, |a, b| a.saturating_add(b));
+    counts.insert(index, count);
+    count
+}
+
+fn enumerate_paths(
+    nodes: &[Node],
+    index: usize,
+    suffix: &mut Vec<usize>,
+    limit: usize,
+    ret: &mut Vec<Vec<usize>>,
+) {
+    if ret.len() >= limit {
+        return;
+    }
+    suffix.push(index);
+    if nodes[index].parents.is_empty() {
+        ret.push(suffix.iter().rev().cloned().collect());
+    } else {
+        for parent in nodes[index].parents.iter() {
+            enumerate_paths(nodes, *parent, suffix, limit, ret);
+        }
+    }
+    suffix.pop();
+}
+
+// RETURNS: the optimal cost, the number of optimal solutions and up to limit of them,
+// or None if the state can not be solved
+pub fn optimal_solutions(
+    state: &State,
+    config: &SolverConfig,
+    limit: usize,
+) -> Option<OptimalSolutions> {
+    let start = initial_state(state, config);
+    let (nodes, goals) = optimal_graph(&start, config);
+    let cost = nodes[*goals.first()?].cost;
+
+    let mut counts = HashMap::new();
+    let count = goals
+        .iter()
+        .map(|g| count_paths(&nodes, *g, &mut counts))
+        .fold(0u128, |a, b| a.saturating_add(b));
+
+    let mut paths = vec![];
+    for goal in goals.iter() {
+        enumerate_paths(&nodes, *goal, &mut vec![], limit, &mut paths);
+    }
+    let solutions = paths
+        .into_iter()
+        .map(|path| {
+            let states = path.into_iter().map(|i| nodes[i].state.clone()).collect();
+            Solution::from_states(states, cost, true)
+        })
+        .collect();
+
+    Some(OptimalSolutions {
+        cost,
+        count,
+        solutions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solver::{solve, SolverConfig};
+    use super::super::state::State;
+    use super::optimal_solutions;
+
+    #[test]
+    fn unique_solution() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let optimal = optimal_solutions(&state, &SolverConfig::default(), 10).unwrap();
+        assert!(optimal.is_unique());
+        assert_eq!(optimal.solutions.len(), 1);
+        assert_eq!(optimal.cost.primary, 2);
+    }
+
+    #[test]
+    fn several_solutions() {
+        // EACH HERO KILLS ONE GHOST IN ANY ORDER (6 WAYS), OR ONE HERO KILLS BOTH (2 WAYS)
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G G @W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let config = SolverConfig::default();
+        let optimal = optimal_solutions(&state, &config, 100).unwrap();
+        let best = solve(&state, &config).unwrap();
+        assert_eq!(optimal.cost, best.cost);
+        assert_eq!(optimal.count, 8);
+        assert_eq!(optimal.solutions.len(), 8);
+        assert!(!optimal.is_unique());
+
+        let limited = optimal_solutions(&state, &config, 2).unwrap();
+        assert_eq!(limited.count, 8);
+        assert_eq!(limited.solutions.len(), 2);
+    }
+
+    #[test]
+    fn no_solution() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ W G  W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert!(optimal_solutions(&state, &SolverConfig::default(), 10).is_none());
+    }
+}
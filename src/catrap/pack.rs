@@ -0,0 +1,363 @@
+// LEVEL PACKS AND THE PROGRESS OF A PLAYER.
+//
+// A PACK IS A TEXT FILE:
+//
+//     ; A COMMENT
+//     pack: First steps
+//     # Only right
+//     par: 2
+//     WWWWWWWWW
+//     W@ G    W
+//     WWWWWWWWW
+//     # Next level
+//     ...
+//
+// EACH LEVEL STARTS WITH "# name", AN OPTIONAL "par: moves" AND THE LINES OF
+// State::from_lines. EMPTY LINES AT THE END OF A LEVEL ARE IGNORED. A FILE WITH THE
+// LINES OF A SINGLE LEVEL AND NO HEADER IS A PACK WITH ONE LEVEL. A LEVEL HAS AT MOST
+// HEIGHT ROWS OF AT MOST WIDTH BLOCKS
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use super::block::Block;
+use super::solver::{solve, Solution, SolverConfig};
+use super::stage::{HEIGHT, WIDTH};
+use super::state::State;
+
+const PACK_PREFIX: &str = "pack:";
+const LEVEL_PREFIX: &str = "#";
+const PAR_PREFIX: &str = "par:";
+const COMMENT_PREFIX: &str = ";";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackLevel {
+    pub name: String,
+    // THE NUMBER OF MOVES OF A GOOD SOLUTION, IF KNOWN
+    pub par: Option<usize>,
+    pub lines: Vec<String>,
+}
+
+impl PackLevel {
+    pub fn state(&self) -> State {
+        State::from_lines(self.lines.iter().map(|l| l.as_str()).collect())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pack {
+    pub name: String,
+    pub levels: Vec<PackLevel>,
+}
+
+fn invalid_data(line: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("line {}: {}", line + 1, message),
+    )
+}
+
+impl Pack {
+    // A LEVEL WITHOUT HEADER, AND A PACK WITHOUT NAME, ARE NAMED default_name
+    pub fn parse(text: &str, default_name: &str) -> std::io::Result<Pack> {
+        let mut pack = Pack {
+            name: default_name.to_string(),
+            levels: vec![],
+        };
+        for (number, line) in text.lines().enumerate() {
+            if line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix(PACK_PREFIX) {
+                if !pack.levels.is_empty() {
+                    return Err(invalid_data(
+                        number,
+                        "the pack name must come before the levels",
+                    ));
+                }
+                pack.name = name.trim().to_string();
+            } else if let Some(name) = line.strip_prefix(LEVEL_PREFIX) {
+                pack.levels.push(PackLevel {
+                    name: name.trim().to_string(),
+                    par: None,
+                    lines: vec![],
+                });
+            } else if let Some(par) = line.strip_prefix(PAR_PREFIX) {
+                let par = par
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid_data(number, "par is not a number"))?;
+                match pack.levels.last_mut() {
+                    Some(level) if level.lines.is_empty() => level.par = Some(par),
+                    _ => return Err(invalid_data(number, "par must follow the name of a level")),
+                }
+            } else {
+                if pack.levels.is_empty() {
+                    if line.is_empty() {
+                        continue;
+                    }
+                    pack.levels.push(PackLevel {
+                        name: default_name.to_string(),
+                        par: None,
+                        lines: vec![],
+                    });
+                }
+                let level = pack.levels.last_mut().unwrap();
+                if let Some(c) = line.chars().find(|c| Block::try_from_char(*c).is_none()) {
+                    return Err(invalid_data(number, &format!("unknown block '{}'", c)));
+                }
+                if line.chars().count() > WIDTH {
+                    return Err(invalid_data(number, &format!("the row is longer than {}", WIDTH)));
+                }
+                if !line.is_empty() && level.lines.len() >= HEIGHT {
+                    return Err(invalid_data(
+                        number,
+                        &format!("the level has more than {} rows", HEIGHT),
+                    ));
+                }
+                level.lines.push(line.to_string());
+            }
+        }
+        for level in pack.levels.iter_mut() {
+            while level.lines.last().is_some_and(|l| l.is_empty()) {
+                level.lines.pop();
+            }
+        }
+        Ok(pack)
+    }
+
+    // THE NAME OF THE FILE, WITHOUT EXTENSION, IS THE DEFAULT NAME
+    pub fn load(path: &Path) -> std::io::Result<Pack> {
+        let text = std::fs::read_to_string(path)?;
+        let default_name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Pack::parse(&text, &default_name)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {}\n", PACK_PREFIX, self.name);
+        for level in self.levels.iter() {
+            text += &format!("{} {}\n", LEVEL_PREFIX, level.name);
+            if let Some(par) = level.par {
+                text += &format!("{} {}\n", PAR_PREFIX, par);
+            }
+            for line in level.lines.iter() {
+                text += line;
+                text += "\n";
+            }
+        }
+        text
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn level(&self, name: &str) -> Option<&PackLevel> {
+        self.levels.iter().find(|l| l.name == name)
+    }
+}
+
+pub struct PackResult<'a> {
+    pub level: &'a PackLevel,
+    pub solution: Option<Solution>,
+}
+
+impl PackResult<'_> {
+    // RETURNS: true if the solution found has more moves than par
+    pub fn over_par(&self) -> bool {
+        match (&self.solution, self.level.par) {
+            (Some(solution), Some(par)) => solution.moves.len() > par,
+            _ => false,
+        }
+    }
+}
+
+pub fn solve_pack<'a>(pack: &'a Pack, config: &SolverConfig) -> Vec<PackResult<'a>> {
+    pack.levels
+        .iter()
+        .map(|level| PackResult {
+            level,
+            solution: solve(&level.state(), config),
+        })
+        .collect()
+}
+
+// THE LEVELS SOLVED BY A PLAYER, WITH THE FEWEST MOVES USED. THE SAVE FILE HAS A
+// LINE FOR EACH LEVEL: PACK NAME, LEVEL NAME AND MOVES, SEPARATED BY TABS
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Progress {
+    best: HashMap<(String, String), usize>,
+}
+
+impl Progress {
+    pub fn parse(text: &str) -> std::io::Result<Progress> {
+        let mut progress = Progress::default();
+        for (number, line) in text.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 3 {
+                return Err(invalid_data(number, "expected pack, level and moves"));
+            }
+            let moves = fields[2]
+                .parse()
+                .map_err(|_| invalid_data(number, "moves is not a number"))?;
+            progress.record(fields[0], fields[1], moves);
+        }
+        Ok(progress)
+    }
+
+    // A MISSING FILE IS A PLAYER THAT HAS NOT SOLVED ANYTHING YET
+    pub fn load(path: &Path) -> std::io::Result<Progress> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Progress::parse(&text),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Progress::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut entries: Vec<_> = self.best.iter().collect();
+        entries.sort();
+        entries
+            .iter()
+            .map(|((pack, level), moves)| format!("{}\t{}\t{}\n", pack, level, moves))
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    // RETURNS: true if moves is the best count for the level
+    pub fn record(&mut self, pack: &str, level: &str, moves: usize) -> bool {
+        let best = self
+            .best
+            .entry((pack.to_string(), level.to_string()))
+            .or_insert(moves);
+        if moves <= *best {
+            *best = moves;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn best(&self, pack: &str, level: &str) -> Option<usize> {
+        self.best
+            .get(&(pack.to_string(), level.to_string()))
+            .copied()
+    }
+
+    pub fn is_solved(&self, pack: &str, level: &str) -> bool {
+        self.best(pack, level).is_some()
+    }
+
+    pub fn solved_count(&self, pack: &Pack) -> usize {
+        pack.levels
+            .iter()
+            .filter(|l| self.is_solved(&pack.name, &l.name))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solver::SolverConfig;
+    use super::super::stage::{HEIGHT, WIDTH};
+    use super::{solve_pack, Pack, Progress};
+    use std::io::ErrorKind;
+
+    const PACK: &str = "; TWO EASY LEVELS
+pack: Easy
+# Only right
+par: 2
+WWWWWWWWW
+W@ G    W
+WWWWWWWWW
+
+# Two ghosts
+WWWWWWWWW
+W@ G    W
+WWWW    W
+WG      W
+WWWWWWWWW
+";
+
+    #[test]
+    fn parse_pack() {
+        let pack = Pack::parse(PACK, "default").unwrap();
+        assert_eq!(pack.name, "Easy");
+        assert_eq!(pack.levels.len(), 2);
+        assert_eq!(pack.levels[0].name, "Only right");
+        assert_eq!(pack.levels[0].par, Some(2));
+        assert_eq!(pack.levels[0].lines.len(), 3);
+        assert_eq!(pack.levels[1].par, None);
+        assert_eq!(pack.level("Two ghosts").unwrap().state().ghosts_count(), 2);
+
+        assert_eq!(Pack::parse(&pack.to_text(), "other").unwrap(), pack);
+    }
+
+    #[test]
+    fn single_level_file() {
+        let pack = Pack::parse("WWWWW\nW@ GW\nWWWWW\n", "level_1").unwrap();
+        assert_eq!(pack.name, "level_1");
+        assert_eq!(pack.levels.len(), 1);
+        assert_eq!(pack.levels[0].name, "level_1");
+    }
+
+    #[test]
+    fn invalid_packs() {
+        assert!(Pack::parse("# a\nW@xGW\n", "").is_err());
+        assert!(Pack::parse("# a\npar: many\n", "").is_err());
+        assert!(Pack::parse("# a\nW@GW\npar: 3\n", "").is_err());
+        assert!(Pack::parse("# a\nW@GW\npack: late\n", "").is_err());
+    }
+
+    #[test]
+    fn oversized_levels() {
+        let row = "W".repeat(WIDTH);
+        let rows = format!("{}\n", row).repeat(HEIGHT);
+        assert!(Pack::parse(&format!("# a\n{}\n\n# b\n", rows), "").is_ok());
+
+        let error = Pack::parse(&format!("# a\n{}W\n", row), "").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2:"));
+
+        let error = Pack::parse(&format!("# a\n{}W\n", rows), "").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with(&format!("line {}:", HEIGHT + 2)));
+    }
+
+    #[test]
+    fn batch_solve() {
+        let pack = Pack::parse(PACK, "").unwrap();
+        let results = solve_pack(&pack, &SolverConfig::default());
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.solution.is_some()));
+        assert_eq!(results[0].solution.as_ref().unwrap().moves.len(), 2);
+        assert!(!results[0].over_par());
+    }
+
+    #[test]
+    fn progress() {
+        let pack = Pack::parse(PACK, "").unwrap();
+        let mut progress = Progress::default();
+        assert!(!progress.is_solved("Easy", "Only right"));
+        assert!(progress.record("Easy", "Only right", 4));
+        assert!(progress.record("Easy", "Only right", 2));
+        assert!(!progress.record("Easy", "Only right", 3));
+        assert_eq!(progress.best("Easy", "Only right"), Some(2));
+        assert_eq!(progress.solved_count(&pack), 1);
+
+        let text = progress.to_text();
+        assert_eq!(text, "Easy\tOnly right\t2\n");
+        assert_eq!(Progress::parse(&text).unwrap(), progress);
+        assert!(Progress::parse("Easy\tOnly right\n").is_err());
+    }
+}
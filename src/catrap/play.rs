@@ -0,0 +1,187 @@
+use std::io::{BufRead, Write};
+
+use super::direction::Direction;
+use super::hint::Hinter;
+use super::pack::PackLevel;
+use super::solver::SolverConfig;
+use super::state::State;
+use crate::terminal::Style;
+
+const HELP: &str =
+    "commands: [hero] l|r|u|d to move (e.g. r, 1 l), z to undo, restart, hint, q to quit";
+
+pub struct PlayOutcome {
+    pub solved: bool,
+    // STEPS OF THE HEROES, WITHOUT THE ONES UNDONE
+    pub moves: usize,
+}
+
+enum Command {
+    Step(usize, Direction),
+    Undo,
+    Restart,
+    Hint,
+    Quit,
+}
+
+fn parse_direction(text: &str) -> Option<Direction> {
+    match text {
+        "l" | "left" => Some(Direction::Left),
+        "r" | "right" => Some(Direction::Right),
+        "u" | "up" => Some(Direction::Up),
+        "d" | "down" => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let line = line.trim().to_lowercase();
+    match line.as_str() {
+        "z" | "undo" => return Some(Command::Undo),
+        "restart" => return Some(Command::Restart),
+        "hint" => return Some(Command::Hint),
+        "q" | "quit" => return Some(Command::Quit),
+        _ => {}
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let hero = if digits == 0 {
+        0
+    } else {
+        line[..digits].parse().ok()?
+    };
+    parse_direction(line[digits..].trim()).map(|d| Command::Step(hero, d))
+}
+
+fn show<W: Write>(
+    state: &State,
+    level: &PackLevel,
+    moves: usize,
+    output: &mut W,
+    style: &Style,
+) -> std::io::Result<()> {
+    state.render(output, style)?;
+    let par = level
+        .par
+        .map(|p| format!("  par: {}", p))
+        .unwrap_or_default();
+    writeln!(
+        output,
+        "{}  moves: {}  ghosts: {}{}",
+        level.name,
+        moves,
+        state.ghosts_count(),
+        par
+    )
+}
+
+// PLAYS A LEVEL WITH ONE COMMAND PER LINE OF input, UNTIL IT IS SOLVED, THE PLAYER
+// QUITS OR input ENDS
+pub fn play<R: BufRead, W: Write>(
+    level: &PackLevel,
+    config: &SolverConfig,
+    input: R,
+    output: &mut W,
+    style: &Style,
+) -> std::io::Result<PlayOutcome> {
+    let mut history = vec![level.state()];
+    let mut hinter = Hinter::new(config.clone());
+    writeln!(output, "{}", HELP)?;
+    show(&history[0], level, 0, output, style)?;
+
+    for line in input.lines() {
+        let line = line?;
+        let current = history.last().unwrap().clone();
+        match parse_command(&line) {
+            None => writeln!(output, "{}", HELP)?,
+            Some(Command::Quit) => break,
+            Some(Command::Undo) => {
+                if history.len() > 1 {
+                    history.pop();
+                }
+            }
+            Some(Command::Restart) => history.truncate(1),
+            Some(Command::Hint) => match hinter.hint(&current) {
+                Some((hero, direction)) => writeln!(output, "hint: hero {} {:?}", hero, direction)?,
+                None => writeln!(output, "no solution from here, undo or restart")?,
+            },
+            Some(Command::Step(hero, direction)) => {
+                let next = if hero < current.heroes_count() {
+                    current.next_state(hero, direction)
+                } else {
+                    None
+                };
+                match next {
+                    Some(next) => history.push(next),
+                    None => writeln!(output, "can not move there")?,
+                }
+            }
+        }
+        let state = history.last().unwrap();
+        show(state, level, history.len() - 1, output, style)?;
+        if state.ghosts_count() == 0 {
+            writeln!(output, "solved in {} moves", history.len() - 1)?;
+            return Ok(PlayOutcome {
+                solved: true,
+                moves: history.len() - 1,
+            });
+        }
+    }
+    Ok(PlayOutcome {
+        solved: false,
+        moves: history.len() - 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::pack::Pack;
+    use super::super::solver::SolverConfig;
+    use super::play;
+    use crate::terminal::Style;
+
+    fn level() -> Pack {
+        Pack::parse(
+            "# Two ghosts\npar: 5\nWWWWWWWWW\nW@ G    W\nWWWW    W\nWG      W\nWWWWWWWWW\n",
+            "",
+        )
+        .unwrap()
+    }
+
+    fn play_with(input: &str) -> (bool, usize, String) {
+        let pack = level();
+        let mut output = vec![];
+        let config = SolverConfig::default();
+        let outcome = play(
+            &pack.levels[0],
+            &config,
+            input.as_bytes(),
+            &mut output,
+            &Style::plain(),
+        )
+        .unwrap();
+        (
+            outcome.solved,
+            outcome.moves,
+            String::from_utf8(output).unwrap(),
+        )
+    }
+
+    #[test]
+    fn solve_by_playing() {
+        // THE HERO CAN NOT CLIMB WITHOUT A STAIR, NOR CAN A HERO THAT IS NOT THERE MOVE
+        let (solved, moves, output) = play_with("u\nr\n7 r\nr\nr\nx\nl\nz\nr\nl\nl\nl\n0 l\n");
+        assert!(solved);
+        assert_eq!(moves, 8);
+        assert!(output.contains("solved in 8 moves"));
+        assert_eq!(output.matches("can not move there").count(), 2);
+    }
+
+    #[test]
+    fn hint_undo_and_quit() {
+        let (solved, moves, output) = play_with("hint\nl\nr\nz\nrestart\nq\nr\n");
+        assert!(!solved);
+        assert_eq!(moves, 0);
+        assert!(output.contains("hint: hero 0 Right"));
+        assert!(output.contains("Two ghosts  moves: 1  ghosts: 2  par: 5"));
+    }
+}
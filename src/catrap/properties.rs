@@ -0,0 +1,303 @@
+// PROPERTIES OF THE MECHANICS CHECKED ON RANDOM STAGES AND RANDOM MOVES. A FAILURE
+// TELLS THE SEED OF THE CASE, THE STAGE AND THE MOVES THAT BROKE THE PROPERTY.
+//
+// THIS IS NOT A PROPERTY TESTING LIBRARY: EVERY CHECK RUNS THE SAME CASES, SEEDS 0 TO
+// CASES - 1 OF Random, AND A FAILING CASE IS NOT SHRUNK. CATRAP_SEED=<seed> RUNS
+// ONLY THE CASE OF THAT SEED
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::block::Block;
+use super::direction::Direction;
+use super::event::{Event, Trace};
+use super::random::Random;
+use super::stage::{Point, HEIGHT, WIDTH};
+use super::state::State;
+
+const CASES: u64 = 300;
+const MOVES_PER_CASE: usize = 40;
+const SEED_VARIABLE: &str = "CATRAP_SEED";
+
+// CHARACTERS OF THE INSIDE OF A RANDOM STAGE, REPEATED BY WEIGHT
+const INSIDE: &str = "                    WWWW*****====RRRGGFF-ab";
+
+fn random_lines(random: &mut Random) -> Vec<String> {
+    let width = 5 + random.below(WIDTH - 4);
+    let height = 5 + random.below(HEIGHT - 4);
+    let inside: Vec<char> = INSIDE.chars().collect();
+    let mut grid: Vec<Vec<char>> = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+                        'W'
+                    } else {
+                        *random.choose(&inside)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let inside_place =
+        |random: &mut Random| (1 + random.below(width - 2), 1 + random.below(height - 2));
+    let mut special = vec!['@'];
+    if random.chance(0.5) {
+        special.push('@');
+    }
+    if random.chance(0.3) {
+        special.extend(['A', 'B']);
+    }
+    if random.chance(0.3) {
+        special.extend(['1', '1']);
+    }
+    let mut taken = vec![];
+    for c in special {
+        let mut place = inside_place(random);
+        while taken.contains(&place) {
+            place = inside_place(random);
+        }
+        taken.push(place);
+        grid[place.1][place.0] = c;
+    }
+    // NOTHING FLOATS AT THE START: WHAT WOULD FALL STANDS ON A WALL
+    for y in 1..height {
+        let (upper, lower) = grid.split_at_mut(y);
+        for (above, below) in upper[y - 1].iter().zip(lower[0].iter_mut()) {
+            if matches!(above, 'R' | 'F' | '@') && *below == ' ' {
+                *below = 'W';
+            }
+        }
+    }
+    grid.iter().map(|line| line.iter().collect()).collect()
+}
+
+fn places() -> impl Iterator<Item = Point> {
+    (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x as i32, y as i32)))
+}
+
+// THE BLOCKS OF A KIND ARE COUNTED TOGETHER: KEYS, DOORS AND TELEPORTERS BY KIND, AND
+// BOTH KINDS OF GHOSTS AS ONE
+fn block_counts(state: &State) -> HashMap<Block, usize> {
+    let mut counts = HashMap::new();
+    for place in places() {
+        let block = match state.stage_block_at(&place) {
+            Block::FallingGhost => Block::Ghost,
+            block => block,
+        };
+        *counts.entry(block).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn hash_of(state: &State) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn unsupported(state: &State) -> Option<Point> {
+    places().find(|place| {
+        let below = Direction::Down.move_point(place);
+        Block::is_fall(state.block_at(place)) && Block::is_empty(state.block_at(&below))
+    })
+}
+
+// RETURNS: the seed of seed alone, or every seed of the cases without it
+fn seeds(seed: Option<&str>) -> std::ops::Range<u64> {
+    match seed {
+        Some(seed) => {
+            let seed: u64 = seed
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("{} is not a number: {}", SEED_VARIABLE, seed));
+            seed..seed + 1
+        }
+        None => 0..CASES,
+    }
+}
+
+// CHECKS EVERY MOVE OF THE RANDOM CASES WITH check, THAT GETS THE STATES BEFORE AND
+// AFTER THE MOVE AND ITS TRACE. PANICS WITH THE FIRST PROPERTY THAT DOES NOT HOLD
+fn check_cases<F>(check: F)
+where
+    F: Fn(&State, &State, &Trace) -> Result<(), String>,
+{
+    let seed = std::env::var(SEED_VARIABLE).ok();
+    for seed in seeds(seed.as_deref()) {
+        let mut random = Random::new(seed);
+        let lines = random_lines(&mut random);
+        let mut state = State::from_lines(lines.iter().map(|l| l.as_str()).collect());
+        let mut moves = vec![];
+        for _ in 0..MOVES_PER_CASE {
+            let hero = random.below(state.heroes_count());
+            let direction = *random.choose(&[
+                Direction::Left,
+                Direction::Right,
+                Direction::Up,
+                Direction::Down,
+            ]);
+            let trace = match state.next_state_traced(hero, direction) {
+                Some(trace) => trace,
+                None => continue,
+            };
+            moves.push((hero, direction));
+            let next = trace.last_state().unwrap().clone();
+            if let Err(message) = check(&state, &next, &trace) {
+                panic!(
+                    "{}\nseed {}, stage:\n{}\nmoves: {:?}\nrun again with {}={}",
+                    message,
+                    seed,
+                    lines.join("\n"),
+                    moves,
+                    SEED_VARIABLE,
+                    seed
+                );
+            }
+            state = next;
+        }
+    }
+}
+
+#[test]
+fn one_seed() {
+    assert_eq!(seeds(None), 0..CASES);
+    assert_eq!(seeds(Some("17")), 17..18);
+    assert!(std::panic::catch_unwind(|| seeds(Some("many"))).is_err());
+}
+
+#[test]
+fn ghosts_count_matches_the_stage() {
+    check_cases(|_, next, _| {
+        let ghosts = places()
+            .filter(|p| Block::is_ghost(next.stage_block_at(p)))
+            .count();
+        if ghosts == next.ghosts_count() {
+            Ok(())
+        } else {
+            Err(format!(
+                "ghosts_count is {}, the stage has {}",
+                next.ghosts_count(),
+                ghosts
+            ))
+        }
+    });
+}
+
+#[test]
+fn blocks_are_conserved() {
+    check_cases(|state, next, trace| {
+        let mut expected = block_counts(state);
+        for event in trace.events() {
+            let place = match event {
+                Event::SandDug(place)
+                | Event::GhostKilled(place)
+                | Event::KeyCollected { at: place, .. }
+                | Event::DoorOpened(place)
+                | Event::FloorCrumbled(place) => place,
+                _ => continue,
+            };
+            let removed = match state.stage_block_at(&place) {
+                Block::FallingGhost => Block::Ghost,
+                block => block,
+            };
+            let count = expected.entry(removed).or_insert(0);
+            *count = count.checked_sub(1).ok_or_else(|| {
+                format!("{:?} removes a {:?} the stage did not have", event, removed)
+            })?;
+            *expected.entry(Block::Empty).or_insert(0) += 1;
+        }
+        expected.retain(|_, count| *count > 0);
+        let counts = block_counts(next);
+        if counts != expected {
+            return Err(format!("blocks {:?}, expected {:?}", counts, expected));
+        }
+        if next.heroes_count() != state.heroes_count() {
+            return Err("the number of heroes changed".to_string());
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn nothing_floats_after_a_move() {
+    check_cases(|_, next, _| match unsupported(next) {
+        Some(place) => Err(format!(
+            "{:?} at {:?} has nothing below",
+            next.block_at(&place),
+            place
+        )),
+        None => Ok(()),
+    });
+}
+
+#[test]
+fn equal_states_have_equal_hashes() {
+    check_cases(|state, next, _| {
+        let direct = state
+            .reachable_moves()
+            .into_iter()
+            .map(|(_, s)| s)
+            .find(|s| s == next)
+            .ok_or("next_state is not among the reachable moves")?;
+        if hash_of(&direct) != hash_of(next) {
+            return Err("equal states with different hashes".to_string());
+        }
+        if next.active_hero().is_none() && next.with_active_hero(0) == *next {
+            return Err("the active hero does not make another state".to_string());
+        }
+        // to_lines REFUSES THE STATES THAT from_lines CAN NOT GIVE BACK
+        if let Some(lines) = next.to_lines() {
+            let rebuilt = State::from_lines(lines.iter().map(|l| l.as_str()).collect());
+            if rebuilt != *next || hash_of(&rebuilt) != hash_of(next) {
+                return Err("from_lines of to_lines is another state".to_string());
+            }
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn equal_states_built_apart() {
+    #[rustfmt::skip]
+    let strings = vec![
+        "WWWWWWWW",
+        "W@  a @W",
+        "WWWWWWWW",
+    ];
+    let state = State::from_lines(strings);
+    let right_first = state
+        .next_state(0, Direction::Right)
+        .unwrap()
+        .next_state(1, Direction::Left)
+        .unwrap();
+    let left_first = state
+        .next_state(1, Direction::Left)
+        .unwrap()
+        .next_state(0, Direction::Right)
+        .unwrap();
+    let rebuilt = State::from_lines(vec!["WWWWWWWW", "W @ a@ W", "WWWWWWWW"]);
+    for other in [&left_first, &rebuilt] {
+        assert!(*other == right_first);
+        assert_eq!(hash_of(other), hash_of(&right_first));
+    }
+    let back = state
+        .next_state(0, Direction::Right)
+        .unwrap()
+        .next_state(0, Direction::Left)
+        .unwrap();
+    assert!(back == state);
+    assert_eq!(hash_of(&back), hash_of(&state));
+
+    // THE SAME STAGE AND HEROES, ONLY THE KEY HELD OR THE ACTIVE HERO DIFFER
+    let keyed = right_first.next_state(1, Direction::Left).unwrap();
+    let without_key = State::from_lines(vec!["WWWWWWWW", "W @ @  W", "WWWWWWWW"]);
+    assert!(keyed.has_key(0) && !without_key.has_key(0));
+    assert_eq!(keyed.heroes(), without_key.heroes());
+    assert!(places().all(|p| keyed.stage_block_at(&p) == without_key.stage_block_at(&p)));
+    assert!(keyed != without_key);
+    assert!(state.with_active_hero(0) != state);
+    assert!(state.with_active_hero(0) != state.with_active_hero(1));
+    assert!(state.with_active_hero(1) == state.with_active_hero(1));
+}
@@ -0,0 +1,447 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use pathfinding::prelude::astar;
+
+use super::cost::{Cost, CostModel, MoveCount};
+use super::ida_star::ida_star;
+use super::macro_moves::{expand_macro_moves, macro_successors};
+use super::moves::Move;
+use super::staged::staged_search;
+use super::state::State;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Algorithm {
+    AStar,
+    // MEMORY BOUNDED: AT MOST table_size STATES ARE REMEMBERED
+    IdaStar { table_size: usize },
+    // CLEARS GHOSTS ONE AT A TIME, TRYING UP TO alternatives SUBGOALS IN EACH STAGE.
+    // NOT OPTIMAL IN GENERAL
+    Staged { alternatives: usize },
+}
+
+impl Algorithm {
+    // RETURNS: true if the solutions found are always optimal for the state
+    fn is_optimal_for(&self, state: &State) -> bool {
+        match self {
+            Algorithm::AStar => true,
+            Algorithm::IdaStar { .. } => true,
+            Algorithm::Staged { .. } => state.ghosts_count() <= 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub one_hero_at_a_time: bool,
+    pub cost_model: Rc<dyn CostModel>,
+    pub algorithm: Algorithm,
+    // SEARCH ONLY BRANCHES ON MOVES THAT CHANGE THE STAGE, WALKING IS DONE IN BETWEEN
+    pub macro_moves: bool,
+    pub limits: Limits,
+}
+
+// WHEN A LIMIT IS REACHED THE SEARCH STOPS EXPANDING STATES AND FAILS
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Limits {
+    pub max_expanded: Option<usize>,
+    pub max_time: Option<Duration>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            one_hero_at_a_time: false,
+            cost_model: Rc::new(MoveCount),
+            algorithm: Algorithm::AStar,
+            macro_moves: false,
+            limits: Limits::default(),
+        }
+    }
+}
+
+pub struct Solution {
+    pub states: Vec<State>,
+    pub moves: Vec<Move>,
+    pub cost: Cost,
+    pub optimal: bool,
+    // NUMBER OF STATES WHOSE SUCCESSORS WERE COMPUTED BY THE SEARCH
+    pub expanded: usize,
+}
+
+impl Solution {
+    pub fn from_states(states: Vec<State>, cost: Cost, optimal: bool) -> Solution {
+        let moves = states
+            .windows(2)
+            .map(|pair| pair[0].move_to(&pair[1]).unwrap())
+            .collect();
+        Solution {
+            states,
+            moves,
+            cost,
+            optimal,
+            expanded: 0,
+        }
+    }
+
+    pub fn switches_count(&self) -> usize {
+        self.moves.iter().filter(|m| m.is_switch()).count()
+    }
+}
+
+pub fn initial_state(state: &State, config: &SolverConfig) -> State {
+    let needs_active_hero = config.one_hero_at_a_time && state.active_hero().is_none();
+    if needs_active_hero && state.heroes_count() > 0 {
+        state.with_active_hero(0)
+    } else {
+        state.clone()
+    }
+}
+
+// RETURNS: the states reachable with one move, with the cost of the move
+pub fn single_moves(state: &State, config: &SolverConfig) -> Vec<(State, Cost)> {
+    state
+        .reachable_moves()
+        .into_iter()
+        .map(|(m, s)| {
+            let cost = config.cost_model.cost(state, &m, &s);
+            (s, cost)
+        })
+        .collect()
+}
+
+pub struct SearchOutcome {
+    pub solution: Option<Solution>,
+    // NUMBER OF STATES WHOSE SUCCESSORS WERE COMPUTED, ALSO WITHOUT SOLUTION
+    pub expanded: usize,
+    pub limit_reached: bool,
+    pub elapsed: Duration,
+}
+
+pub fn solve(state: &State, config: &SolverConfig) -> Option<Solution> {
+    search(state, config).solution
+}
+
+pub fn search(state: &State, config: &SolverConfig) -> SearchOutcome {
+    let started = Instant::now();
+    let single_moves = |st: &State| single_moves(st, config);
+    let expanded = Cell::new(0);
+    let limit_reached = Cell::new(false);
+    let successors = |st: &State| {
+        let limits = &config.limits;
+        if limits.max_expanded.is_some_and(|max| expanded.get() >= max)
+            || limits.max_time.is_some_and(|max| started.elapsed() >= max)
+        {
+            limit_reached.set(true);
+        }
+        if limit_reached.get() {
+            return vec![];
+        }
+        expanded.set(expanded.get() + 1);
+        if config.macro_moves {
+            macro_successors(st, &single_moves)
+        } else {
+            single_moves(st)
+        }
+    };
+    let heuristic = |_st: &State| Cost::default();
+    let success = |st: &State| st.ghosts_count() == 0;
+    let start = initial_state(state, config);
+    let ret = match config.algorithm {
+        Algorithm::AStar => astar(&start, successors, heuristic, success),
+        Algorithm::IdaStar { table_size } => ida_star(&start, successors, success, table_size),
+        Algorithm::Staged { alternatives } => staged_search(&start, successors, alternatives),
+    };
+    // A SOLUTION FOUND AFTER THE LIMIT MAY HAVE MISSED A CHEAPER ONE
+    let optimal = config.algorithm.is_optimal_for(&start) && !limit_reached.get();
+
+    let solution = ret.map(|(states, cost)| {
+        let states = if config.macro_moves {
+            expand_macro_moves(states, &single_moves)
+        } else {
+            states
+        };
+        let mut solution = Solution::from_states(states, cost, optimal);
+        solution.expanded = expanded.get();
+        solution
+    });
+    SearchOutcome {
+        solution,
+        expanded: expanded.get(),
+        limit_reached: limit_reached.get(),
+        elapsed: started.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::super::cost::{MovesAndSwitches, Pushes, SandDug};
+    use super::super::state::State;
+    use super::{search, solve, Algorithm, Limits, SolverConfig};
+
+    fn two_heroes() -> State {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@G  G@ W",
+            "WWWWWWWWW",
+        ];
+        State::from_lines(strings)
+    }
+
+    #[test]
+    fn all_heroes_at_once() {
+        let solution = solve(&two_heroes(), &SolverConfig::default()).unwrap();
+        assert_eq!(solution.cost.primary, 2);
+        assert_eq!(solution.switches_count(), 0);
+    }
+
+    #[test]
+    fn one_hero_at_a_time_free_switches() {
+        let config = SolverConfig {
+            one_hero_at_a_time: true,
+            ..Default::default()
+        };
+        let solution = solve(&two_heroes(), &config).unwrap();
+        solution.states.iter().for_each(|s| s.dump_stdout());
+        assert_eq!(solution.cost.primary, 2);
+        assert_eq!(solution.cost.moves, 3);
+        assert_eq!(solution.switches_count(), 1);
+        assert_eq!(solution.moves.len(), 3);
+    }
+
+    #[test]
+    fn one_hero_at_a_time_paid_switches() {
+        let config = SolverConfig {
+            one_hero_at_a_time: true,
+            cost_model: Rc::new(MovesAndSwitches),
+            ..Default::default()
+        };
+        let solution = solve(&two_heroes(), &config).unwrap();
+        assert_eq!(solution.cost.primary, 3);
+        assert_eq!(solution.moves.len(), 3);
+    }
+
+    #[test]
+    fn minimize_sand_dug() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W       W",
+            "W =WWWW W",
+            "W@=*  G W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+
+        let shortest = solve(&state, &SolverConfig::default()).unwrap();
+        assert_eq!(shortest.cost.primary, 5);
+
+        let config = SolverConfig {
+            cost_model: Rc::new(SandDug),
+            ..Default::default()
+        };
+        let solution = solve(&state, &config).unwrap();
+        solution.states.iter().for_each(|s| s.dump_stdout());
+        assert_eq!(solution.cost.primary, 0);
+        assert!(solution.cost.moves > 5);
+    }
+
+    #[test]
+    fn minimize_pushes() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@RG    W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let config = SolverConfig {
+            cost_model: Rc::new(Pushes),
+            ..Default::default()
+        };
+        assert!(solve(&state, &config).is_none());
+
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@R G   W",
+            "WWW WWWWW",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let solution = solve(&state, &config).unwrap();
+        assert_eq!(solution.cost.primary, 1);
+        assert_eq!(solution.cost.moves, 4);
+    }
+
+    #[test]
+    fn ida_star_finds_the_same_cost() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let astar = solve(&state, &SolverConfig::default()).unwrap();
+        for table_size in [0, 16, 100_000].iter() {
+            let config = SolverConfig {
+                algorithm: Algorithm::IdaStar {
+                    table_size: *table_size,
+                },
+                ..Default::default()
+            };
+            let ida = solve(&state, &config).unwrap();
+            assert_eq!(ida.cost, astar.cost);
+            assert_eq!(ida.states.last().unwrap().ghosts_count(), 0);
+        }
+
+        let config = SolverConfig {
+            one_hero_at_a_time: true,
+            cost_model: Rc::new(MovesAndSwitches),
+            algorithm: Algorithm::IdaStar { table_size: 1000 },
+            ..Default::default()
+        };
+        assert_eq!(solve(&two_heroes(), &config).unwrap().cost.primary, 3);
+    }
+
+    #[test]
+    fn ida_star_without_solution() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ W G  W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let config = SolverConfig {
+            algorithm: Algorithm::IdaStar { table_size: 1000 },
+            ..Default::default()
+        };
+        assert!(solve(&state, &config).is_none());
+    }
+
+    #[test]
+    fn staged_search() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let astar = solve(&state, &SolverConfig::default()).unwrap();
+        assert!(astar.optimal);
+
+        let config = SolverConfig {
+            algorithm: Algorithm::Staged { alternatives: 3 },
+            ..Default::default()
+        };
+        let staged = solve(&state, &config).unwrap();
+        staged.states.iter().for_each(|s| s.dump_stdout());
+        assert!(!staged.optimal);
+        assert!(staged.cost >= astar.cost);
+        assert_eq!(staged.states.last().unwrap().ghosts_count(), 0);
+        assert_eq!(staged.moves.len(), staged.states.len() - 1);
+    }
+
+    #[test]
+    fn staged_search_backtracks() {
+        // KILLING THE NEAREST GHOST FIRST DROPS THE HERO WHERE THE OTHER ONE IS UNREACHABLE
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "WG  @ G W",
+            "WWWWWW WW",
+            "W     WWW",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let config = SolverConfig {
+            algorithm: Algorithm::Staged { alternatives: 1 },
+            ..Default::default()
+        };
+        assert!(solve(&state, &config).is_none());
+
+        let config = SolverConfig {
+            algorithm: Algorithm::Staged { alternatives: 2 },
+            ..Default::default()
+        };
+        let staged = solve(&state, &config).unwrap();
+        assert_eq!(staged.states.last().unwrap().ghosts_count(), 0);
+    }
+
+    #[test]
+    fn macro_moves() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWWWW",
+            "W@ =   G  W",
+            "WWW=WWWW* W",
+            "W  =    *GW",
+            "WWWWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let single = solve(&state, &SolverConfig::default()).unwrap();
+        for algorithm in [Algorithm::AStar, Algorithm::IdaStar { table_size: 1000 }].iter() {
+            let config = SolverConfig {
+                algorithm: *algorithm,
+                macro_moves: true,
+                ..Default::default()
+            };
+            let macro_solution = solve(&state, &config).unwrap();
+            macro_solution.states.iter().for_each(|s| s.dump_stdout());
+            assert_eq!(macro_solution.cost, single.cost);
+            assert_eq!(macro_solution.moves.len(), single.moves.len());
+        }
+
+        let config = SolverConfig {
+            one_hero_at_a_time: true,
+            cost_model: Rc::new(MovesAndSwitches),
+            macro_moves: true,
+            ..Default::default()
+        };
+        let solution = solve(&two_heroes(), &config).unwrap();
+        assert_eq!(solution.cost.primary, 3);
+        assert_eq!(solution.moves.len(), 3);
+    }
+
+    #[test]
+    fn limits() {
+        #[rustfmt::skip]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let unlimited = search(&state, &SolverConfig::default());
+        assert!(!unlimited.limit_reached);
+        assert!(unlimited.solution.unwrap().optimal);
+
+        for algorithm in [Algorithm::AStar, Algorithm::IdaStar { table_size: 1000 }].iter() {
+            let config = SolverConfig {
+                algorithm: *algorithm,
+                limits: Limits {
+                    max_expanded: Some(3),
+                    max_time: None,
+                },
+                ..Default::default()
+            };
+            let limited = search(&state, &config);
+            assert!(limited.limit_reached);
+            assert!(limited.solution.is_none());
+            assert_eq!(limited.expanded, 3);
+        }
+    }
+}
@@ -48,4 +48,16 @@ impl Stage {
         let y = y as usize;
         self.blocks[y][x] = block;
     }
+
+    // RETURNS: the location of the other teleporter with the same label
+    pub fn teleporter_partner(&self, location: &Point) -> Option<Point> {
+        let label = match self.block_at(location) {
+            Block::Teleporter(label) => label,
+            _ => return None,
+        };
+        (0..self.height())
+            .flat_map(|y| (0..self.width()).map(move |x| (x as i32, y as i32)))
+            .filter(|p| p != location)
+            .find(|p| matches!(self.block_at(p), Block::Teleporter(l) if l == label))
+    }
 }
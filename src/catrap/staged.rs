@@ -0,0 +1,69 @@
+use pathfinding::num_traits::Zero;
+use pathfinding::prelude::{build_path, dijkstra_partial};
+
+use super::cost::Cost;
+use super::state::State;
+
+// RETURNS: up to alternatives states with fewer ghosts than start, cheapest first,
+// each one with the path that leads to it
+fn subgoals<FN>(start: &State, successors: &FN, alternatives: usize) -> Vec<(Vec<State>, Cost)>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    let ghosts = start.ghosts_count();
+    let mut found: Vec<State> = vec![];
+    let (parents, _) = dijkstra_partial(
+        start,
+        |st: &State| {
+            if st.ghosts_count() < ghosts {
+                vec![]
+            } else {
+                successors(st)
+            }
+        },
+        |st: &State| {
+            if st.ghosts_count() < ghosts {
+                found.push(st.clone());
+            }
+            found.len() >= alternatives
+        },
+    );
+
+    let mut ret: Vec<(Vec<State>, Cost)> = found
+        .iter()
+        .map(|st| (build_path(st, &parents), parents[st].1))
+        .collect();
+    // KILLING MORE GHOSTS AT ONCE IS PREFERRED WHEN THE COST IS THE SAME
+    ret.sort_by_key(|(path, cost)| (*cost, path.last().unwrap().ghosts_count()));
+    ret
+}
+
+// CLEARS THE GHOSTS ONE STAGE AT A TIME: EACH STAGE LOOKS FOR THE CHEAPEST STATES WITH
+// FEWER GHOSTS AND TRIES THEM IN ORDER, BACKTRACKING WHEN A STAGE LEADS NOWHERE.
+// THE RESULT IS ONLY GUARANTEED TO BE OPTIMAL WHEN THERE IS ONE STAGE
+pub fn staged_search<FN>(
+    start: &State,
+    successors: FN,
+    alternatives: usize,
+) -> Option<(Vec<State>, Cost)>
+where
+    FN: Fn(&State) -> Vec<(State, Cost)>,
+{
+    fn stage<FN>(start: &State, successors: &FN, alternatives: usize) -> Option<(Vec<State>, Cost)>
+    where
+        FN: Fn(&State) -> Vec<(State, Cost)>,
+    {
+        if start.ghosts_count() == 0 {
+            return Some((vec![start.clone()], Cost::zero()));
+        }
+        for (path, cost) in subgoals(start, successors, alternatives) {
+            if let Some((rest, rest_cost)) = stage(path.last().unwrap(), successors, alternatives) {
+                let mut ret = path;
+                ret.extend(rest.into_iter().skip(1));
+                return Some((ret, cost + rest_cost));
+            }
+        }
+        None
+    }
+    stage(start, &successors, alternatives.max(1))
+}
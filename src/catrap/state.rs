@@ -7,7 +7,10 @@ use super::block::Block;
 use super::stage::Stage;
 
 use super::direction::Direction;
+use super::event::{Event, Trace};
+use super::moves::Move;
 use super::stage::Point;
+use crate::terminal::{self, Style};
 
 
 #[derive(Clone,Eq,PartialEq)]
@@ -15,12 +18,21 @@ pub struct State {
     stage: Rc<RefCell<Stage>>,
     heroes: Vec<Point>,
     ghosts_count: usize,
+    // INVENTORY: one bit per key kind, shared by all the heroes
+    keys: u8,
+    // WHEN SOME, ONLY THIS HERO CAN MOVE AND SWITCHING TO ANOTHER ONE IS A MOVE
+    active_hero: Option<usize>,
 }
 
+// STATES ARE USED AS KEYS: THEIR STAGE IS BEHIND A RefCell, BUT A SHARED STAGE IS
+// COPIED BEFORE BEING MODIFIED, SO THE HASH OF A STATE NEVER CHANGES AND
+// clippy::mutable_key_type CAN BE ALLOWED WHERE THEY ARE KEYS
 impl Hash for State{
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.stage.borrow().hash(state);
         self.heroes.hash(state);
+        self.keys.hash(state);
+        self.active_hero.hash(state);
     }
 }
 
@@ -40,13 +52,13 @@ impl State {
             count
         };
 
-        let ret = State {
+        State {
             stage: Rc::new(RefCell::new(stage)),
             heroes,
             ghosts_count,
-        };
-
-        ret
+            keys: 0,
+            active_hero: None,
+        }
     }
 
     #[allow(dead_code)]
@@ -58,6 +70,40 @@ impl State {
         self.ghosts_count
     }
 
+    pub fn heroes_count(&self) -> usize {
+        self.heroes.len()
+    }
+
+    pub fn heroes(&self) -> &[Point] {
+        &self.heroes
+    }
+
+    pub fn active_hero(&self) -> Option<usize> {
+        self.active_hero
+    }
+
+    pub fn with_active_hero(&self, hero_index: usize) -> State {
+        assert!(hero_index < self.heroes.len());
+        let mut ret = self.clone();
+        ret.active_hero = Some(hero_index);
+        ret
+    }
+
+    // RETURNS: true if both states only differ in the heroes
+    pub fn same_stage(&self, other: &State) -> bool {
+        let same_blocks = Rc::ptr_eq(&self.stage, &other.stage) || self.stage == other.stage;
+        same_blocks && self.keys == other.keys
+    }
+
+    pub fn has_key(&self, kind: u8) -> bool {
+        self.keys & (1 << kind) != 0
+    }
+
+    fn collect_key(&mut self, kind: u8) -> &mut Self {
+        self.keys |= 1 << kind;
+        self
+    }
+
     fn move_hero(&mut self, hero_index: usize, to: Point) -> &mut Self {
         self.heroes[hero_index] = to;
         self
@@ -69,15 +115,21 @@ impl State {
         self
     }
 
-    fn block_at(&self, location: &Point) -> Block {
+    pub fn block_at(&self, location: &Point) -> Block {
         let block = self.stage.borrow().block_at(location);
-        if self.heroes.contains(&location) && matches!(block, Block::Empty) {
+        let overlaps_hero = matches!(block, Block::Empty | Block::Teleporter(_));
+        if self.heroes.contains(location) && overlaps_hero {
             Block::Hero
         } else {
             self.stage.borrow().block_at(location)
         }
     }
 
+    // RETURNS: the block of the stage, even where a hero stands
+    pub fn stage_block_at(&self, location: &Point) -> Block {
+        self.stage.borrow().block_at(location)
+    }
+
     fn modify(&mut self, location: &Point, block: Block) -> &mut Self {
         if matches!(block, Block::Hero){
             panic!();
@@ -89,11 +141,23 @@ impl State {
         if Block::is_ghost(block) {
             self.ghosts_count += 1;
         }
+        if Rc::strong_count(&self.stage) > 1 {
+            // THE STAGE IS SHARED WITH OTHER STATES, SO IT CAN NOT BE MODIFIED IN PLACE
+            self.copy_stage();
+        }
         self.stage.borrow_mut().set_block_at(location, block);
         self
     }
 
-    fn free_fall_column(&mut self, empty_location: &Point) -> &mut Self {
+    // ADDS THE EVENT TO THE TRACE, IF THERE IS ONE, WITH A COPY OF THE STATE AFTER IT
+    fn emit(&mut self, trace: &mut Option<Trace>, event: Event) -> &mut Self {
+        if let Some(trace) = trace {
+            trace.steps.push((event, self.clone()));
+        }
+        self
+    }
+
+    fn free_fall_column(&mut self, empty_location: &Point, trace: &mut Option<Trace>) -> &mut Self {
         //println!("free_fall_column({:?}):", empty_location);
         if !Block::is_empty(self.block_at(empty_location)) {
             return self;
@@ -119,44 +183,80 @@ impl State {
                 None
             }
         }
-        let mut next_empty_location = free_fall_step(self, empty_location);
-        while next_empty_location.is_some() {
-            next_empty_location = free_fall_step(self, &next_empty_location.unwrap());
+        let mut location = *empty_location;
+        while let Some(up) = free_fall_step(self, &location) {
+            let block = self.block_at(&location);
+            self.emit(trace, Event::Fell { block, from: up, to: location });
+            location = up;
         }
         //println!("  free_fall_column: recursión abajo",);
-        self.free_fall_column(&Direction::Down.move_point(empty_location));
+        self.free_fall_column(&Direction::Down.move_point(empty_location), trace);
+        self
+    }
+
+    fn crumble_floor_under(&mut self, from: &Point, trace: &mut Option<Trace>) -> &mut Self {
+        let under = Direction::Down.move_point(from);
+        let left = !self.heroes.contains(from);
+        if left && matches!(self.block_at(&under), Block::CrumblingFloor) {
+            self.modify(&under, Block::Empty).emit(trace, Event::FloorCrumbled(under));
+        }
         self
     }
 
-    fn free_fall_after_move(&mut self, from: &Point, to: &Point, next_to: &Point) -> &Self {
+    fn free_fall_after_move(
+        &mut self,
+        from: &Point,
+        to: &Point,
+        next_to: &Point,
+        trace: &mut Option<Trace>,
+    ) -> &Self {
         //println!("---- from ----- ");
-        self.free_fall_column(from);
+        self.free_fall_column(from, trace);
         //println!("---- to ----- ");
-        self.free_fall_column(to);
+        self.free_fall_column(to, trace);
         //println!("---- to down ----- ");
-        self.free_fall_column(&Direction::Down.move_point(to));
+        self.free_fall_column(&Direction::Down.move_point(to), trace);
         //println!("---- next_to down ----- ");
-        self.free_fall_column(&Direction::Down.move_point(next_to));
+        self.free_fall_column(&Direction::Down.move_point(next_to), trace);
         self
     }
 
-    fn apply_modifications<FN: FnOnce(&mut Self) -> ()>(
+    fn apply_modifications<FN: FnOnce(&mut Self, &mut Option<Trace>)>(
         &mut self,
         copy_stage: bool,
         hero: &Point,
         to: &Point,
         next_to: &Point,
+        trace: &mut Option<Trace>,
         function: FN,
     ) -> &mut Self {
         if copy_stage {
             self.copy_stage();
         }
-        function(self);
-        self.free_fall_after_move(&hero, &to, &next_to);
+        function(self, trace);
+        self.crumble_floor_under(hero, trace);
+        self.free_fall_after_move(hero, to, next_to, trace);
         self
     }
 
     pub fn next_state(&self, hero_index: usize, direction: Direction) -> Option<Self> {
+        self.next_state_recording(hero_index, direction, &mut None)
+    }
+
+    // RETURNS: like next_state, with what happened during the move. The state after
+    // the last event is the next state
+    pub fn next_state_traced(&self, hero_index: usize, direction: Direction) -> Option<Trace> {
+        let mut trace = Some(Trace::default());
+        self.next_state_recording(hero_index, direction, &mut trace)?;
+        trace
+    }
+
+    fn next_state_recording(
+        &self,
+        hero_index: usize,
+        direction: Direction,
+        trace: &mut Option<Trace>,
+    ) -> Option<Self> {
         use super::block::Block::*;
         assert!(hero_index < self.heroes.len());
         let hero = self.heroes[hero_index];
@@ -172,20 +272,24 @@ impl State {
 
         match (to_block, next_to_block, horizontal) {
             (Empty, _, true) => {
-                ret.apply_modifications(false, &hero, &to, &next_to, |myself| {
+                ret.apply_modifications(false, &hero, &to, &next_to, trace, |myself, trace| {
                     myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
                 });
                 Some(ret)
             }
             (Empty, _, false) if matches!(hero_block,Block::Stair) => {
-                ret.apply_modifications(false, &hero, &to, &next_to, |myself| {
+                ret.apply_modifications(false, &hero, &to, &next_to, trace, |myself, trace| {
                     myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
                 });
                 Some(ret)
             }
             (SandWall, _, true) => {
-                ret.apply_modifications(true, &hero, &to, &next_to, |myself| {
-                    myself.modify(&to.clone(),Empty).move_hero(hero_index, to);
+                ret.apply_modifications(true, &hero, &to, &next_to, trace, |myself, trace| {
+                    myself.modify(&to.clone(),Empty).emit(trace, Event::SandDug(to));
+                    myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
                 });
                 Some(ret)
             }
@@ -193,36 +297,190 @@ impl State {
                 if matches!(direction, Direction::Up) && matches!(hero_block, Block::Empty) {
                     None
                 } else {
-                    ret.apply_modifications(false, &hero, &to, &next_to, |myself| {
+                    ret.apply_modifications(false, &hero, &to, &next_to, trace, |myself, trace| {
                         myself.move_hero(hero_index, to);
+                        myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
                     });
                     Some(ret)
                 }
             }
             (Rock, Empty, true) => {
-                ret.apply_modifications(true, &hero, &to, &next_to, |myself| {
+                ret.apply_modifications(true, &hero, &to, &next_to, trace, |myself, trace| {
                     myself.modify(&to, Empty).modify(&next_to, Rock);
+                    myself.emit(trace, Event::RockPushed { from: to, to: next_to });
                 });
 
                 Some(ret)
             }
+            (Key(kind), _, true) => {
+                ret.apply_modifications(true, &hero, &to, &next_to, trace, |myself, trace| {
+                    myself.modify(&to, Empty).collect_key(kind);
+                    myself.emit(trace, Event::KeyCollected { kind, at: to });
+                    myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
+                });
+                Some(ret)
+            }
+            (Door(kind), _, true) if self.has_key(kind) => {
+                ret.apply_modifications(true, &hero, &to, &next_to, trace, |myself, trace| {
+                    myself.modify(&to, Empty).emit(trace, Event::DoorOpened(to));
+                    myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
+                });
+                Some(ret)
+            }
+            (Teleporter(_), _, true) => {
+                let destination = self.stage.borrow().teleporter_partner(&to)?;
+                if self.hero_index_at(&destination).is_some() {
+                    return None;
+                }
+                ret.apply_modifications(false, &hero, &to, &next_to, trace, |myself, trace| {
+                    myself.move_hero(hero_index, destination);
+                    let to = destination;
+                    myself.emit(trace, Event::Teleported { hero: hero_index, from: hero, to });
+                    myself.free_fall_column(&Direction::Down.move_point(&destination), trace);
+                });
+                Some(ret)
+            }
             (ghost, _, true) if Block::is_ghost(ghost) => {
-                ret.apply_modifications(true, &hero, &to, &next_to, |myself| {
-                    myself.modify(&to, Empty).move_hero(hero_index, to);
+                ret.apply_modifications(true, &hero, &to, &next_to, trace, |myself, trace| {
+                    myself.modify(&to, Empty).emit(trace, Event::GhostKilled(to));
+                    myself.move_hero(hero_index, to);
+                    myself.emit(trace, Event::HeroMoved { hero: hero_index, from: hero, to });
                 });
                 Some(ret)
             }
             _ => None,
         }
     }
-    pub fn reachable_states_one_move(&self) -> Vec<State> {
-        (0..(self.heroes.len()))
-            .flat_map(|hero_index| {
-                Direction::iterator().map(move |d| self.next_state(hero_index, d.clone()))
+    pub fn reachable_moves(&self) -> Vec<(Move, State)> {
+        let steps = |hero_index: usize| {
+            Direction::iterator().filter_map(move |d| {
+                self.next_state(hero_index, *d)
+                    .map(|s| (Move::Step(hero_index, *d), s))
             })
-            .filter(|o| o.is_some())
-            .map(|o| o.unwrap())
-            .collect()
+        };
+        match self.active_hero {
+            None => (0..self.heroes.len()).flat_map(steps).collect(),
+            Some(active) => {
+                let switches = (0..self.heroes.len())
+                    .filter(|h| *h != active)
+                    .map(|h| (Move::Switch(h), self.with_active_hero(h)));
+                steps(active).chain(switches).collect()
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn reachable_states_one_move(&self) -> Vec<State> {
+        self.reachable_moves().into_iter().map(|(_, s)| s).collect()
+    }
+
+    // RETURNS: the move that leads from this state to the next one
+    pub fn move_to(&self, next: &State) -> Option<Move> {
+        self.reachable_moves()
+            .into_iter()
+            .find(|(_, s)| s == next)
+            .map(|(m, _)| m)
+    }
+
+    // RETURNS: the same state, with the falling blocks stacked on location moved one
+    // place up, as they were before falling into location. None if they could not be there
+    fn lift_column(&self, location: &Point) -> Option<State> {
+        let mut top = *location;
+        while Block::is_fall(self.stage.borrow().block_at(&top)) {
+            top = Direction::Up.move_point(&top);
+        }
+        if top == *location || !Block::is_empty(self.block_at(&top)) {
+            return None;
+        }
+        let mut ret = self.clone();
+        let mut empty = top;
+        while empty != *location {
+            let below = Direction::Down.move_point(&empty);
+            let block = ret.block_at(&below);
+            ret.modify(&empty, block).modify(&below, Block::Empty);
+            empty = below;
+        }
+        Some(ret)
+    }
+
+    // RETURNS: states that may lead to this one when the hero moves in direction. They
+    // are only candidates, previous_states checks them with next_state. A collected
+    // key, an opened door, a crumbled floor or a teleport are not undone: the states
+    // before them are never candidates
+    fn un_move_candidates(&self, hero_index: usize, direction: Direction) -> Vec<State> {
+        use super::block::Block::*;
+        let hero = self.heroes[hero_index];
+        let back = direction.opposite();
+        let mut ret = vec![];
+
+        // THE HERO MOVED INTO landing AND THEN FELL TO ITS CURRENT LOCATION. THE
+        // HERO MAY HAVE DUG SAND OR KILLED A GHOST AT landing, AND THE BLOCKS ABOVE
+        // THE PREVIOUS LOCATION MAY HAVE FALLEN INTO IT
+        let mut landing = hero;
+        loop {
+            let from = back.move_point(&landing);
+            if Block::is_empty(self.block_at(&from)) || Block::is_fall(self.block_at(&from)) {
+                for restored in [Empty, SandWall, Ghost, FallingGhost].iter() {
+                    let mut candidate = self.clone();
+                    candidate.move_hero(hero_index, from);
+                    if !Block::is_empty(*restored) {
+                        candidate.modify(&landing, *restored);
+                    }
+                    if Block::is_empty(self.block_at(&from)) {
+                        ret.push(candidate);
+                    } else if let Some(lifted) = candidate.lift_column(&from) {
+                        ret.push(lifted);
+                    }
+                }
+            }
+            let up = Direction::Up.move_point(&landing);
+            if !Block::is_empty(self.block_at(&up)) {
+                break;
+            }
+            landing = up;
+        }
+
+        // THE HERO PUSHED A ROCK, THAT MAY HAVE FALLEN AFTER THAT
+        if direction.is_horizontal() {
+            let to = direction.move_point(&hero);
+            let mut rock = direction.move_point(&to);
+            while Block::is_empty(self.block_at(&rock)) {
+                rock = Direction::Down.move_point(&rock);
+            }
+            if matches!(self.block_at(&rock), Rock) {
+                let mut candidate = self.clone();
+                candidate.modify(&rock, Empty);
+                if Block::is_empty(self.block_at(&to)) {
+                    candidate.modify(&to, Rock);
+                    ret.push(candidate);
+                } else if let Some(mut lifted) = candidate.lift_column(&to) {
+                    lifted.modify(&to, Rock);
+                    ret.push(lifted);
+                }
+            }
+        }
+        ret
+    }
+
+    // RETURNS: the states that lead to this one with a single move (un-moves). Walking,
+    // climbing, digging, killing ghosts and pushing rocks are undone, with the falls
+    // that happened after them. Keys, doors, crumbling floors and teleporters are not:
+    // the moves that used them are missing
+    pub fn previous_states(&self) -> Vec<(Move, State)> {
+        let mut ret: Vec<(Move, State)> = vec![];
+        for hero_index in 0..self.heroes.len() {
+            for direction in Direction::iterator() {
+                for candidate in self.un_move_candidates(hero_index, *direction) {
+                    let leads_here = candidate.next_state(hero_index, *direction).as_ref() == Some(self);
+                    if leads_here && !ret.iter().any(|(_, s)| *s == candidate) {
+                        ret.push((Move::Step(hero_index, *direction), candidate));
+                    }
+                }
+            }
+        }
+        ret
     }
 
     pub fn dump_stdout(&self) {
@@ -246,13 +504,66 @@ impl State {
                 let c = if !is_hero { block.to_char() } else { '@' };
                 write!(output, "{}", c)?;
             }
-            write!(output, "\n")?;
+            writeln!(output)?;
         }
-        write!(output, "\n")?;
-        write!(output, "\n")?;
+        writeln!(output)?;
+        writeln!(output)?;
         Ok(())
     }
 
+    // LIKE dump, WITH THE GLYPHS AND COLOURS OF THE STYLE
+    pub fn render<W: Write>(&self, output: &mut W, style: &Style) -> std::io::Result<()> {
+        let size = (self.width(), self.height());
+        terminal::write_grid(output, "", "", size, style, |column, line| {
+            let point = (column as i32, line as i32);
+            if self.heroes.contains(&point) {
+                Block::Hero.glyph()
+            } else {
+                self.block_at(&point).glyph()
+            }
+        })?;
+        writeln!(output)?;
+        writeln!(output)
+    }
+
+    // RETURNS: the lines that from_lines reads, the drawn_size rectangle of the stage.
+    // None if from_lines can not give the state back: keys are held, a hero is active,
+    // a hero stands over a stair or a teleporter, or the heroes are not in reading order
+    pub fn to_lines(&self) -> Option<Vec<String>> {
+        let covered = self.heroes.iter().any(|h| !Block::is_empty(self.stage_block_at(h)));
+        let in_order = self.heroes.windows(2).all(|pair| (pair[0].1, pair[0].0) < (pair[1].1, pair[1].0));
+        if self.keys != 0 || self.active_hero.is_some() || covered || !in_order {
+            return None;
+        }
+        let (width, height) = self.drawn_size();
+        let lines = (0..height)
+            .map(|line| {
+                (0..width)
+                    .map(|column| {
+                        let point = (column as i32, line as i32);
+                        if self.heroes.contains(&point) { '@' } else { self.block_at(&point).to_char() }
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(lines)
+    }
+
+    // RETURNS: the number of columns and lines worth drawing: the walls after the last
+    // block that is not a wall are left out, but one wall is kept to close the stage
+    pub fn drawn_size(&self) -> (usize, usize) {
+        let (mut width, mut height) = (0, 0);
+        for line in 0..self.height() {
+            for column in 0..self.width() {
+                if self.stage_block_at(&(column as i32, line as i32)) != Block::Wall {
+                    width = width.max(column + 1);
+                    height = height.max(line + 1);
+                }
+            }
+        }
+        ((width + 1).min(self.width()), (height + 1).min(self.height()))
+    }
+
     pub fn from_lines(lines: Vec<&str>) -> State {
         let blocks = [Default::default(); super::stage::HEIGHT];
         let mut ret = Stage { blocks };
@@ -278,10 +589,15 @@ impl State {
 }
 
 #[cfg(test)]
+// THE TESTS ARE KEPT AS THEY WERE WRITTEN, WITH cfg_attr FOR rustfmt
+#[allow(clippy::deprecated_cfg_attr, clippy::assertions_on_constants)]
 mod tests {
     use super::super::block::Block;
     use super::super::direction::Direction;
+    use super::super::moves::Move;
     use super::super::state::State;
+    use crate::terminal::Style;
+    use std::rc::Rc;
 
     #[test]
     fn whole_turn_around() {
@@ -472,4 +788,298 @@ mod tests {
         assert!(matches!(next_state.block_at(&(4, 2)), Block::Empty));
         assert!(matches!(next_state.block_at(&(4, 4)), Block::Hero));
     }
+
+    #[test]
+    fn crumbling_floor() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W  R    W",
+            "W @R    W",
+            "WW--W   W",
+            "W   W   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        state.dump_stdout();
+        let next_state = state.next_state(0, Direction::Left).unwrap();
+        next_state.dump_stdout();
+        assert!(matches!(next_state.block_at(&(2, 3)), Block::Empty));
+        assert!(matches!(next_state.block_at(&(1, 2)), Block::Hero));
+
+        // THE ORIGINAL STATE MUST NOT BE AFFECTED
+        assert!(matches!(state.block_at(&(2, 3)), Block::CrumblingFloor));
+        assert!(matches!(state.block_at(&(2, 2)), Block::Hero));
+    }
+
+    #[test]
+    fn shared_stage() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let mut changed = state.clone();
+        assert!(Rc::ptr_eq(&state.stage, &changed.stage));
+
+        // THE CLONE GETS ITS OWN STAGE, THE ORIGINAL STATE MUST NOT CHANGE WITH IT
+        changed.modify(&(3, 1), Block::Empty);
+        assert!(!Rc::ptr_eq(&state.stage, &changed.stage));
+        assert!(matches!(state.block_at(&(3, 1)), Block::Ghost));
+        assert!(matches!(changed.block_at(&(3, 1)), Block::Empty));
+        assert_eq!(state.ghosts_count(), 1);
+        assert_eq!(changed.ghosts_count(), 0);
+        assert!(state != changed);
+    }
+
+    #[test]
+    fn crumbling_floor_cascade() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W  R    W",
+            "W  @    W",
+            "WWW-W   W",
+            "W   W   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        state.dump_stdout();
+        let next_state = state.next_state(0, Direction::Left).unwrap();
+        next_state.dump_stdout();
+
+        // THE HERO LEFT (3,2): THE FLOOR CRUMBLES AND THE ROCK FALLS THROUGH IT
+        assert!(matches!(next_state.block_at(&(3, 1)), Block::Empty));
+        assert!(matches!(next_state.block_at(&(3, 3)), Block::Empty));
+        assert!(matches!(next_state.block_at(&(3, 4)), Block::Rock));
+        assert!(matches!(next_state.block_at(&(2, 2)), Block::Hero));
+
+        assert!(matches!(state.block_at(&(3, 1)), Block::Rock));
+    }
+
+    #[test]
+    fn teleporter() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@1W 1  W",
+            "WWWW    W",
+            "W       W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        state.dump_stdout();
+        let next_state = state.next_state(0, Direction::Right).unwrap();
+        next_state.dump_stdout();
+
+        // THE HERO APPEARS AT THE OTHER TELEPORTER AND FALLS FROM THERE
+        assert!(matches!(next_state.block_at(&(5, 1)), Block::Teleporter(1)));
+        assert!(matches!(next_state.block_at(&(5, 3)), Block::Hero));
+        assert!(matches!(next_state.block_at(&(2, 1)), Block::Teleporter(1)));
+    }
+
+    #[test]
+    fn teleporter_without_partner() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@2 1   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert!(state.next_state(0, Direction::Right).is_none());
+    }
+
+    #[test]
+    fn one_hero_at_a_time() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W @  @  W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        assert_eq!(state.reachable_moves().len(), 4);
+
+        let state = state.with_active_hero(1);
+        let moves = state.reachable_moves();
+        assert_eq!(moves.len(), 3);
+        assert!(moves.iter().all(|(m, _)| !matches!(m, Move::Step(0, _))));
+
+        let (_, switched) = moves.iter().find(|(m, _)| m.is_switch()).unwrap();
+        assert_eq!(switched.active_hero(), Some(0));
+        assert!(switched != &state);
+        assert_eq!(state.move_to(switched), Some(Move::Switch(0)));
+    }
+
+    #[test]
+    fn render() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+        let strings = vec![
+            "WWWWWW",
+            "W@=GaW",
+            "W*R-1W",
+            "WWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let mut dump = vec![];
+        state.dump(&mut dump).unwrap();
+        let mut plain = vec![];
+        state.render(&mut plain, &Style::plain()).unwrap();
+        assert_eq!(plain, dump);
+
+        let unicode = Style { unicode: true, colour: false };
+        let mut output = vec![];
+        state.render(&mut output, &unicode).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().nth(1).unwrap().starts_with("█☻╫Ωa█"));
+        assert!(output.lines().nth(2).unwrap().starts_with("█▒●░1█"));
+
+        let colour = Style { unicode: true, colour: true };
+        let mut output = vec![];
+        state.render(&mut output, &colour).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("\x1b[32m☻\x1b[0m"));
+    }
+
+    #[test]
+    fn to_lines() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W@ G  a-W",
+            "WWW=WW3 W",
+            "  W*  3 W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings.clone());
+        let lines = state.to_lines().unwrap();
+        assert_eq!(lines, strings);
+        let again = State::from_lines(lines.iter().map(|l| l.as_str()).collect());
+        assert!(again == state);
+
+        // THE KEY IS HELD
+        let right = state.next_state(0, Direction::Right).unwrap();
+        let mut key = right.clone();
+        while !key.has_key(0) {
+            key = key.next_state(0, Direction::Right).unwrap();
+        }
+        assert!(key.to_lines().is_none());
+        assert!(right.with_active_hero(0).to_lines().is_none());
+
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWW",
+            "W@ @=GW",
+            "WW WWWW",
+            "WWWWWWW",
+        ];
+        let state = State::from_lines(strings.clone());
+        assert_eq!(state.to_lines().unwrap(), strings);
+        // THE SECOND HERO ON THE STAIR
+        let on_stair = state.next_state(1, Direction::Right).unwrap();
+        assert!(on_stair.to_lines().is_none());
+        // THE FIRST HERO FALLS BELOW THE SECOND ONE
+        let below = state.next_state(0, Direction::Right).unwrap();
+        assert!(below.to_lines().is_none());
+    }
+
+    #[test]
+    fn previous_states() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W  R  F W",
+            "W @R *  W",
+            "WW=W  W W",
+            "W =     W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        for (a_move, next) in state.reachable_moves() {
+            next.dump_stdout();
+            let previous = next.previous_states();
+            assert!(previous.iter().any(|(m, s)| *m == a_move && *s == state));
+            for (m, s) in previous {
+                match m {
+                    Move::Step(hero, direction) => assert!(s.next_state(hero, direction).unwrap() == next),
+                    Move::Switch(_) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn previous_states_after_falls() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W  R    W",
+            "W  R    W",
+            "W @R G  W",
+            "WWWW *  W",
+            "W    W  W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let mut current = state.clone();
+        for direction in [Direction::Right, Direction::Right, Direction::Right, Direction::Right].iter() {
+            let next = current.next_state(0, *direction).unwrap();
+            next.dump_stdout();
+            assert!(next.previous_states().iter().any(|(_, s)| *s == current));
+            current = next;
+        }
+    }
+
+    #[test]
+    fn key_opens_door() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W A@ a  W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        state.dump_stdout();
+        assert!(state.next_state(0, Direction::Left).is_none());
+
+        let next_state = state.next_state(0, Direction::Right).unwrap();
+        let next_state = next_state.next_state(0, Direction::Right).unwrap();
+        next_state.dump_stdout();
+        assert!(next_state.has_key(0));
+        assert!(matches!(next_state.block_at(&(5, 1)), Block::Hero));
+
+        let next_state = (0..3).fold(next_state, |s, _| s.next_state(0, Direction::Left).unwrap());
+        next_state.dump_stdout();
+        assert!(matches!(next_state.block_at(&(2, 1)), Block::Hero));
+        assert!(next_state.next_state(0, Direction::Left).is_some());
+    }
+
+    #[test]
+    fn wrong_key_keeps_door_closed() {
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W B@b   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let next_state = state.next_state(0, Direction::Right).unwrap();
+        assert!(next_state.has_key(1));
+        assert!(!next_state.has_key(0));
+        let next_state = next_state.next_state(0, Direction::Left).unwrap();
+        assert!(next_state.next_state(0, Direction::Left).is_some());
+
+        #[cfg_attr(rustfmt, rustfmt::skip)]
+            let strings = vec![
+            "WWWWWWWWW",
+            "W A@b   W",
+            "WWWWWWWWW",
+        ];
+        let state = State::from_lines(strings);
+        let next_state = state.next_state(0, Direction::Right).unwrap();
+        let next_state = next_state.next_state(0, Direction::Left).unwrap();
+        assert!(next_state.next_state(0, Direction::Left).is_none());
+    }
 }
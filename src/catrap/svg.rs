@@ -0,0 +1,306 @@
+use std::io::Write;
+
+use super::block::Block;
+use super::state::State;
+
+// SIZE OF A CELL, IN PIXELS
+const CELL: usize = 32;
+// ROOM FOR THE COORDINATES ABOVE AND LEFT OF A STAGE
+const MARGIN: usize = 16;
+// ROOM FOR THE CAPTION BELOW EACH STATE OF A FILMSTRIP
+const CAPTION: usize = 20;
+
+// COLOURS OF THE KEYS AND DOORS, BY KIND
+const KIND_COLOURS: [&str; 4] = ["#e04040", "#4080e0", "#40c040", "#e0c040"];
+
+fn write_block<W: Write>(block: Block, x: usize, y: usize, output: &mut W) -> std::io::Result<()> {
+    let (cx, cy) = (x + CELL / 2, y + CELL / 2);
+    match block {
+        Block::Empty | Block::Hero => {}
+        Block::Wall => writeln!(
+            output,
+            r##"<rect class="wall" x="{}" y="{}" width="{}" height="{}" fill="#707070" stroke="#505050"/>"##,
+            x, y, CELL, CELL
+        )?,
+        Block::SandWall => writeln!(
+            output,
+            r##"<rect class="sand" x="{}" y="{}" width="{}" height="{}" fill="#d8b868" stroke="#a08040" stroke-dasharray="4 2"/>"##,
+            x, y, CELL, CELL
+        )?,
+        Block::Rock => writeln!(
+            output,
+            r##"<circle class="rock" cx="{}" cy="{}" r="{}" fill="#8b5a2b"/>"##,
+            cx,
+            cy,
+            CELL / 2 - 2
+        )?,
+        Block::Ghost | Block::FallingGhost => {
+            let dashes = if block == Block::FallingGhost {
+                r#" stroke-dasharray="3 2""#
+            } else {
+                ""
+            };
+            let class = if block == Block::FallingGhost {
+                "falling-ghost"
+            } else {
+                "ghost"
+            };
+            writeln!(
+                output,
+                r##"<path class="{}" d="M{} {} a{} {} 0 0 1 {} 0 v{} l-4 -4 l-4 4 l-4 -4 l-4 4 l-4 -4 l-4 4 z" fill="#e8e8ff" stroke="#8080c0"{}/>"##,
+                class,
+                x + 4,
+                cy,
+                CELL / 2 - 4,
+                CELL / 2 - 4,
+                CELL - 8,
+                CELL / 2 - 4,
+                dashes
+            )?
+        }
+        Block::Stair => {
+            writeln!(
+                output,
+                r##"<g class="stair" stroke="#c08040" stroke-width="3"><line x1="{}" y1="{}" x2="{}" y2="{}"/><line x1="{}" y1="{}" x2="{}" y2="{}"/>"##,
+                x + 8,
+                y,
+                x + 8,
+                y + CELL,
+                x + CELL - 8,
+                y,
+                x + CELL - 8,
+                y + CELL
+            )?;
+            for rung in 1..4 {
+                let ry = y + rung * CELL / 4;
+                writeln!(
+                    output,
+                    r##"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"##,
+                    x + 8,
+                    ry,
+                    x + CELL - 8,
+                    ry
+                )?;
+            }
+            writeln!(output, "</g>")?
+        }
+        Block::Key(kind) => writeln!(
+            output,
+            r##"<g class="key" fill="none" stroke="{}" stroke-width="3"><circle cx="{}" cy="{}" r="5"/><line x1="{}" y1="{}" x2="{}" y2="{}"/></g>"##,
+            KIND_COLOURS[kind as usize % KIND_COLOURS.len()],
+            x + 10,
+            cy,
+            x + 15,
+            cy,
+            x + CELL - 6,
+            cy
+        )?,
+        Block::Door(kind) => writeln!(
+            output,
+            r##"<rect class="door" x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#303030" stroke-width="2"/>"##,
+            x + 4,
+            y,
+            CELL - 8,
+            CELL,
+            KIND_COLOURS[kind as usize % KIND_COLOURS.len()]
+        )?,
+        Block::CrumblingFloor => writeln!(
+            output,
+            r##"<rect class="crumbling" x="{}" y="{}" width="{}" height="{}" fill="#a0a0a0" stroke="#505050" stroke-dasharray="2 2"/>"##,
+            x,
+            y + CELL / 2,
+            CELL,
+            CELL / 2
+        )?,
+        Block::Teleporter(label) => writeln!(
+            output,
+            r##"<g class="teleporter"><circle cx="{}" cy="{}" r="{}" fill="none" stroke="#c040c0" stroke-width="3"/><text x="{}" y="{}" font-size="12" text-anchor="middle" fill="#c040c0">{}</text></g>"##,
+            cx,
+            cy,
+            CELL / 2 - 3,
+            cx,
+            cy + 4,
+            label
+        )?,
+    }
+    Ok(())
+}
+
+// WRITES THE COORDINATES, THE STAGE AND THE HEROES WITH (left, top) AS THE TOP LEFT CORNER
+fn write_state<W: Write>(
+    state: &State,
+    left: usize,
+    top: usize,
+    output: &mut W,
+) -> std::io::Result<()> {
+    let (width, height) = state.drawn_size();
+    let (x0, y0) = (left + MARGIN, top + MARGIN);
+    writeln!(
+        output,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#202020"/>"##,
+        x0,
+        y0,
+        width * CELL,
+        height * CELL
+    )?;
+    for column in 0..width {
+        writeln!(
+            output,
+            r##"<text class="coordinate" x="{}" y="{}" font-size="10" text-anchor="middle" fill="#808080">{}</text>"##,
+            x0 + column * CELL + CELL / 2,
+            y0 - 4,
+            column
+        )?;
+    }
+    for line in 0..height {
+        writeln!(
+            output,
+            r##"<text class="coordinate" x="{}" y="{}" font-size="10" text-anchor="middle" fill="#808080">{}</text>"##,
+            left + MARGIN / 2,
+            y0 + line * CELL + CELL / 2 + 4,
+            line
+        )?;
+    }
+    for line in 0..height {
+        for column in 0..width {
+            let block = state.block_at(&(column as i32, line as i32));
+            write_block(block, x0 + column * CELL, y0 + line * CELL, output)?;
+        }
+    }
+    for (index, (x, y)) in state.heroes().iter().enumerate() {
+        let (cx, cy) = (
+            x0 + *x as usize * CELL + CELL / 2,
+            y0 + *y as usize * CELL + CELL / 2,
+        );
+        let stroke = if state.active_hero() == Some(index) {
+            "#ffffff"
+        } else {
+            "#206020"
+        };
+        writeln!(
+            output,
+            r##"<g class="hero"><circle cx="{}" cy="{}" r="{}" fill="#40c040" stroke="{}" stroke-width="2"/><text x="{}" y="{}" font-size="12" text-anchor="middle" fill="#000000">{}</text></g>"##,
+            cx,
+            cy,
+            CELL / 2 - 4,
+            stroke,
+            cx,
+            cy + 4,
+            index
+        )?;
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(width: usize, height: usize, output: &mut W) -> std::io::Result<()> {
+    writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="monospace">"#,
+        width, height, width, height
+    )
+}
+
+pub fn write_svg<W: Write>(state: &State, output: &mut W) -> std::io::Result<()> {
+    let (width, height) = state.drawn_size();
+    write_header(MARGIN + width * CELL, MARGIN + height * CELL, output)?;
+    write_state(state, 0, 0, output)?;
+    writeln!(output, "</svg>")
+}
+
+// LAYS OUT THE STATES OF A SOLUTION IN A GRID OF columns COLUMNS, WITH THE STEP
+// NUMBER UNDER EACH OF THEM
+pub fn write_filmstrip<W: Write>(
+    states: &[State],
+    columns: usize,
+    output: &mut W,
+) -> std::io::Result<()> {
+    let columns = columns.max(1).min(states.len().max(1));
+    let rows = states.len().div_ceil(columns);
+    let (width, height) = states
+        .iter()
+        .map(State::drawn_size)
+        .fold((1, 1), |(w, h), (sw, sh)| (w.max(sw), h.max(sh)));
+    let frame_width = MARGIN + width * CELL + MARGIN;
+    let frame_height = MARGIN + height * CELL + CAPTION;
+    write_header(columns * frame_width, rows * frame_height, output)?;
+    for (index, state) in states.iter().enumerate() {
+        let left = (index % columns) * frame_width;
+        let top = (index / columns) * frame_height;
+        write_state(state, left, top, output)?;
+        writeln!(
+            output,
+            r##"<text class="caption" x="{}" y="{}" font-size="12" text-anchor="middle" fill="#404040">{}</text>"##,
+            left + MARGIN + width * CELL / 2,
+            top + frame_height - 6,
+            if index == 0 {
+                "start".to_string()
+            } else {
+                format!("step {}", index)
+            }
+        )?;
+    }
+    writeln!(output, "</svg>")
+}
+
+pub fn to_svg(state: &State) -> String {
+    let mut output = vec![];
+    write_svg(state, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+pub fn filmstrip_to_svg(states: &[State], columns: usize) -> String {
+    let mut output = vec![];
+    write_filmstrip(states, columns, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solve_catrap;
+    use super::super::state::State;
+    use super::{filmstrip_to_svg, to_svg};
+
+    #[test]
+    fn blocks_and_heroes() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W@ R=G aW",
+            "W**-1A1FW",
+            "WWWWWWWWW",
+        ];
+        let svg = to_svg(&State::from_lines(strings));
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"class="wall""#).count(), 22);
+        assert_eq!(svg.matches(r#"class="sand""#).count(), 2);
+        assert_eq!(svg.matches(r#"class="rock""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="stair""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="ghost""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="falling-ghost""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="key""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="door""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="crumbling""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="teleporter""#).count(), 2);
+        assert_eq!(svg.matches(r#"class="hero""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="coordinate""#).count(), 9 + 4);
+    }
+
+    #[test]
+    fn filmstrip() {
+        #[rustfmt::skip]
+        let strings = vec![
+            "WWWWWWWWW",
+            "W@ G    W",
+            "WWWW    W",
+            "WG      W",
+            "WWWWWWWWW",
+        ];
+        let states = solve_catrap(&State::from_lines(strings)).unwrap();
+        let svg = filmstrip_to_svg(&states, 4);
+        assert_eq!(svg.matches(r#"class="hero""#).count(), states.len());
+        assert_eq!(svg.matches(r#"class="caption""#).count(), states.len());
+        assert!(svg.contains(">start<"));
+        assert!(svg.contains(&format!(">step {}<", states.len() - 1)));
+    }
+}
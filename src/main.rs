@@ -1,10 +1,33 @@
-// mod catrap;
+mod catrap;
 
+// THE LINTS OF amazonas ARE LEFT AS THEY WERE
+#[allow(
+    dead_code,
+    clippy::assertions_on_constants,
+    clippy::clone_on_copy,
+    clippy::deprecated_cfg_attr,
+    clippy::let_and_return,
+    clippy::ptr_arg,
+    clippy::question_mark,
+    clippy::redundant_locals,
+    clippy::single_match
+)]
 mod amazonas;
 
+mod terminal;
+
 fn main() {
     //catrap::level_80();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(message) = catrap::run_cli(&args) {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let board = amazonas::find_board_minimize_trees(8);
     board.unwrap().dump_stdout("");
 }
@@ -0,0 +1,159 @@
+use std::io::{IsTerminal, Write};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Colour {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+}
+
+impl Colour {
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Magenta => 35,
+            Colour::Cyan => 36,
+            Colour::White => 97,
+            Colour::Gray => 90,
+        }
+    }
+}
+
+// HOW A CELL IS WRITTEN: unicode IS USED WHEN THE STYLE ALLOWS IT, ascii OTHERWISE
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph {
+    pub ascii: char,
+    pub unicode: char,
+    pub colour: Option<Colour>,
+}
+
+impl Glyph {
+    pub fn new(ascii: char, unicode: char, colour: Option<Colour>) -> Glyph {
+        Glyph {
+            ascii,
+            unicode,
+            colour,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Style {
+    pub unicode: bool,
+    pub colour: bool,
+}
+
+impl Style {
+    // THE ORIGINAL ASCII OUTPUT
+    #[cfg(test)]
+    pub fn plain() -> Style {
+        Style {
+            unicode: false,
+            colour: false,
+        }
+    }
+
+    // UNICODE GLYPHS, WITH COLOURS ONLY IF STDOUT IS A TERMINAL AND NO_COLOR IS NOT SET
+    pub fn for_stdout() -> Style {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        Style {
+            unicode: true,
+            colour: std::io::stdout().is_terminal() && !no_color,
+        }
+    }
+}
+
+pub fn write_glyph<W: Write>(output: &mut W, glyph: &Glyph, style: &Style) -> std::io::Result<()> {
+    let symbol = if style.unicode {
+        glyph.unicode
+    } else {
+        glyph.ascii
+    };
+    match glyph.colour {
+        Some(colour) if style.colour => {
+            write!(output, "\x1b[{}m{}\x1b[0m", colour.ansi_code(), symbol)
+        }
+        _ => write!(output, "{}", symbol),
+    }
+}
+
+// WRITES THE GLYPH IN REVERSE VIDEO, OR AS IS WHEN COLOURS ARE NOT USED
+pub fn write_highlighted_glyph<W: Write>(
+    output: &mut W,
+    glyph: &Glyph,
+    style: &Style,
+) -> std::io::Result<()> {
+    if !style.colour {
+        return write_glyph(output, glyph, style);
+    }
+    write!(output, "\x1b[7m")?;
+    write_glyph(output, glyph, style)?;
+    write!(output, "\x1b[0m")
+}
+
+// WRITES A GRID OF GLYPHS, EACH LINE STARTING WITH prefix AND EACH GLYPH FOLLOWED BY separator
+pub fn write_grid<W: Write, F: Fn(usize, usize) -> Glyph>(
+    output: &mut W,
+    prefix: &str,
+    separator: &str,
+    (width, height): (usize, usize),
+    style: &Style,
+    glyph_at: F,
+) -> std::io::Result<()> {
+    for line in 0..height {
+        write!(output, "{}", prefix)?;
+        for column in 0..width {
+            write_glyph(output, &glyph_at(column, line), style)?;
+            write!(output, "{}", separator)?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_grid, Colour, Glyph, Style};
+
+    fn checkerboard(style: &Style) -> String {
+        let mut output = vec![];
+        write_grid(&mut output, "> ", "", (2, 2), style, |x, y| {
+            if (x + y) % 2 == 0 {
+                Glyph::new('#', '█', Some(Colour::Gray))
+            } else {
+                Glyph::new('.', '·', None)
+            }
+        })
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn styles() {
+        let plain = checkerboard(&Style::plain());
+        assert_eq!(plain, "> #.\n> .#\n");
+
+        let unicode = Style {
+            unicode: true,
+            colour: false,
+        };
+        assert_eq!(checkerboard(&unicode), "> █·\n> ·█\n");
+
+        let colour = Style {
+            unicode: true,
+            colour: true,
+        };
+        assert_eq!(
+            checkerboard(&colour),
+            "> \x1b[90m█\x1b[0m·\n> ·\x1b[90m█\x1b[0m\n"
+        );
+    }
+}